@@ -6,7 +6,7 @@ use std::time::Duration;
 
 use PhyzViz::utils::ODEs;
 use PhyzViz::utils::ODEs::ODEFunc;
-use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, add_ribbon_position};
+use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, MeshRibbonPlugin, RibbonMaterial};
 
 #[cfg(feature = "fps_overlay")]
 use bevy::dev_tools::fps_overlay::FpsOverlayPlugin;
@@ -77,7 +77,7 @@ impl ODEs::ODEFunc for DoublePendulum {
 }
 
 
-fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<RibbonMaterial>>, asset_server: Res<AssetServer>) {
     commands.spawn((
         Camera2d,
     ));
@@ -226,13 +226,13 @@ fn main() {
             .set(TimePlugin::default()),
         )
         .add_plugins(Shape2dPlugin::default())
+        .add_plugins(MeshRibbonPlugin)
         .insert_resource(ClearColor(bevy::prelude::Color::Srgba(Srgba { red: 84.0 / 255.0, green: 18.0 / 255.0, blue: 18.0 / 255.0, alpha: 1.0 })))
         .add_systems(Startup, setup )
         // Physics on a fixed timestep
         .add_systems(FixedUpdate, step_pendulum)
         // Rendering on the variable-rate Update schedule (interpolation optional)
-        .add_systems(Update, draw_pendulum)
-        .add_systems(Update, add_ribbon_position);
+        .add_systems(Update, draw_pendulum);
 
     #[cfg(feature = "fps_overlay")]
     app.add_plugins(FpsOverlayPlugin::default());
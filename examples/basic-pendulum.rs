@@ -5,7 +5,7 @@ use bevy_vector_shapes::prelude::*;
 use std::time::Duration;
 
 use PhyzViz::utils::rk4;
-use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, add_ribbon_position};
+use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, MeshRibbonPlugin, RibbonMaterial};
 use PhyzViz::utils::ODEs::ODEFunc;
 use PhyzViz::utils::ODEs;
 use bevy::{
@@ -41,7 +41,7 @@ impl ODEs::ODEFunc for SimplePendulum {
     }
 }
 
-fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<RibbonMaterial>>, asset_server: Res<AssetServer>) {
     commands.spawn((
         Camera2d,
     ));
@@ -150,11 +150,11 @@ fn main() {
         )
         .insert_resource(Time::<Fixed>::from_duration(Duration::from_secs_f64(1.0 / 60.0)))
         .add_plugins(Shape2dPlugin::default())
+        .add_plugins(MeshRibbonPlugin)
         .insert_resource(ClearColor(bevy::prelude::Color::Srgba(Srgba { red: 84.0 / 255.0, green: 18.0 / 255.0, blue: 18.0 / 255.0, alpha: 1.0 })))
         .add_systems(Startup, setup)
         .add_systems(FixedUpdate, step_pendulum)
-        .add_systems(Update, draw_pendulum)
-        .add_systems(Update, add_ribbon_position);
+        .add_systems(Update, draw_pendulum);
 
     #[cfg(feature = "fps_overlay")]
     app.add_plugins(FpsOverlayPlugin::default());
@@ -4,10 +4,18 @@ use bevy::time::{Fixed, TimePlugin};
 use bevy_vector_shapes::prelude::*;
 use std::time::Duration;
 
-use PhyzViz::utils::ODEs;
-use PhyzViz::utils::rk4::{self, RK4Prealloc};
-use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, add_ribbon_position};
-use PhyzViz::utils::graph::{spawn_graph_widget, GraphParams, GridlineConfig, draw_graph_widget};
+use PhyzViz::utils::rk4::RK4;
+use PhyzViz::utils::simulation::Simulation;
+use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, MeshRibbonPlugin, RibbonClock, RibbonMaterial, RibbonTarget};
+use PhyzViz::utils::graph::{spawn_graph_widget, spawn_multi_series_graph_widget, GraphBackend, GraphParams, GraphSeries, GridlineConfig, RangeMode, SeriesStyle, draw_graph_widget};
+use PhyzViz::utils::graph_export::GraphExportPlugin;
+use PhyzViz::utils::graph_interaction::GraphInteractionPlugin;
+use PhyzViz::utils::graph_overlay::{GraphAnchor, GraphOverlayPlugin, GraphPlacement};
+use PhyzViz::models::double_pendulum::DoublePendulum;
+use PhyzViz::utils::invariants::{spawn_invariant_monitor, monitor_invariants, SimulationState};
+use PhyzViz::utils::probe::{probe_graphs, Observables, Probe};
+use PhyzViz::utils::spectrum::{spawn_spectrum_widget, SpectrumParams, draw_spectrum_widget};
+use PhyzViz::utils::figure::{FigureCapture, FigureExportPlugin};
 use bevy::{
     core_pipeline::tonemapping::{DebandDither, Tonemapping},
     post_process::bloom::{Bloom},
@@ -18,108 +26,66 @@ use bevy::dev_tools::fps_overlay::FpsOverlayPlugin;
 
 const RENDER_SCALE: f32 = 60.0;
 
-pub struct DoublePendulum {
-    pub m1: f32,
-    pub m2: f32,
-    pub l1: f32,
-    pub l2: f32,
-    pub g: f32,
-}
+/// Bob followed by a ribbon (0 or 1), positioned relative to its parent in simulation units
+#[derive(Component)]
+struct Bob(usize);
 
 #[derive(Resource)]
 struct PendulumState {
-    theta1: f32,       // Angular displacement of the first pendulum (radians)
-    omega1: f32,       // Angular velocity of the first pendulum (radians/s)
-    theta2: f32,       // Angular displacement of the second pendulum (radians)
-    omega2: f32,       // Angular velocity of the second pendulum (radians/s)
     params: DoublePendulum,
-    prealloc : RK4Prealloc,
+    sim: Simulation,   // State vector is [theta1, omega1, theta2, omega2] (radians, radians/s)
 }
 
-// Source : https://web.mit.edu/jorloff/www/chaosTalk/double-pendulum/double-pendulum-en.html
-impl ODEs::ODEFunc for DoublePendulum {
-    fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
-        // State variables
-        let theta1 = y[0];
-        let omega1 = y[1];
-        let theta2 = y[2];
-        let omega2 = y[3];
-
-        let m1 = self.m1;
-        let m2 = self.m2;
-        let l1 = self.l1;
-        let l2 = self.l2;
-        let g = self.g;
-
-        // Common terms
-        let delta = theta1 - theta2;
-        let denom = 2.0 * m1 + m2 - m2 * (2.0 * theta1 - 2.0 * theta2).cos();
-
-        // Equations of motion
-        let dtheta1_dt = omega1;
-        let dtheta2_dt = omega2;
-
-        out[0] = dtheta1_dt;
-        out[2] = dtheta2_dt;
-
-        let domega1_dt = (
-            -g * (2.0 * m1 + m2) * theta1.sin()
-            - m2 * g * (theta1 - 2.0 * theta2).sin()
-            - 2.0 * m2 * delta.sin()
-                * (omega2.powi(2) * l2 + omega1.powi(2) * l1 * delta.cos())
-        ) / (l1 * denom);
-
-        out[1] = domega1_dt;
-
-        let domega2_dt = (
-            2.0 * delta.sin()
-                * (omega1.powi(2) * l1 * (m1 + m2)
-                + g * (m1 + m2) * theta1.cos()
-                + omega2.powi(2) * l2 * m2 * delta.cos())
-        ) / (l2 * denom);
-
-        out[3] = domega2_dt;
+impl PendulumState {
+    fn theta1(&self) -> f32 { self.sim.state()[0] }
+    fn omega1(&self) -> f32 { self.sim.state()[1] }
+    fn theta2(&self) -> f32 { self.sim.state()[2] }
+    fn omega2(&self) -> f32 { self.sim.state()[3] }
+
+    fn kinetic_energy(&self) -> (f32, f32) {
+        self.params.kinetic_energy(self.theta1(), self.omega1(), self.theta2(), self.omega2())
+    }
+
+    fn potential_energy(&self) -> (f32, f32) {
+        self.params.potential_energy(self.theta1(), self.theta2())
+    }
+
+    /// Positions of the first bob from the pivot and of the second bob from the first,
+    /// with the rods drawn 2 units long
+    fn bob_offsets(&self) -> (Vec3, Vec3) {
+        let (length1, length2) = (2.0, 2.0);
+        let (theta1, theta2) = (self.theta1(), self.theta2());
+        (
+            Vec3::new(length1 * theta1.sin(), -length1 * theta1.cos(), 0.0),
+            Vec3::new(length2 * theta2.sin(), -length2 * theta2.cos(), 0.0),
+        )
     }
 }
 
-impl DoublePendulum {
-    /// Calculate kinetic energy of the system
-    fn kinetic_energy(&self, theta1: f32, omega1: f32, theta2: f32, omega2: f32) -> (f32, f32) {
-        let m1 = self.m1;
-        let m2 = self.m2;
-        let l1 = self.l1;
-        let l2 = self.l2;
-        
-        let delta = theta1 - theta2;
-        
-        // Kinetic energy formula for double pendulum
-        let ke1 = 0.5 * m1 * (l1 * omega1).powi(2);
-        let ke2 = 0.5 * m2 * (
-            (l1 * omega1).powi(2) + (l2 * omega2).powi(2) 
-            + 2.0 * l1 * l2 * omega1 * omega2 * delta.cos()
-        );
-
-        (ke1, ke2)
+impl SimulationState for PendulumState {
+    fn state(&self) -> Vec<f32> {
+        self.sim.state().to_vec()
     }
-    
-    /// Calculate potential energy of the system
-    fn potential_energy(&self, theta1: f32, theta2: f32) -> (f32, f32) {
-        let m1 = self.m1;
-        let m2 = self.m2;
-        let l1 = self.l1;
-        let l2 = self.l2;
-        let g = self.g;
-        
-        // Taking the pivot as zero potential energy reference
-        let h1 = -l1 * theta1.cos();
-        let h2 = -l1 * theta1.cos() - l2 * theta2.cos();
-
-        (m1 * g * h1, m2 * g * h2)
+
+    fn time(&self) -> f32 {
+        self.sim.time()
+    }
+}
+
+impl Observables for PendulumState {
+    fn observable(&self, name: &str) -> Option<f32> {
+        match name {
+            "theta1" => Some(self.theta1()),
+            "omega1" => Some(self.omega1()),
+            "theta2" => Some(self.theta2()),
+            "omega2" => Some(self.omega2()),
+            _ => None,
+        }
     }
 }
 
 
-fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<RibbonMaterial>>, asset_server: Res<AssetServer>, time_fixed: Res<Time<Fixed>>) {
     commands.spawn((
         Camera2d,
         Tonemapping::TonyMcMapface, // 1. Using a tonemapper that desaturates to white is recommended
@@ -127,34 +93,41 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
         DebandDither::Enabled,      // Optional: bloom causes gradients which cause banding
     ));
 
-    let prealloc = rk4::RK4Prealloc {
-        y0: vec![0.0; 4],
-        k1: vec![0.0; 4],
-        k2: vec![0.0; 4],
-        k3: vec![0.0; 4],
-        k4: vec![0.0; 4],
-        out: vec![0.0; 4],
-        func: Box::new(DoublePendulum { m1: 1.0, m2: 1.0, l1: 1.0, l2: 1.0, g: 9.81 }),
-    };
-
-    // commands.insert_resource(PendulumState { theta1: 2.899002795870406, omega1: 0.0, theta2: 1.913720799888307, omega2: 0.0, params: DoublePendulum { m1: 1.0, m2: 1.0, l1: 1.0, l2: 1.0, g: 9.81 }, prealloc });
-    commands.insert_resource(PendulumState { theta1: 2.0, omega1: 0.0, theta2: 2.0, omega2: 0.0, params: DoublePendulum { m1: 1.0, m2: 1.0, l1: 1.0, l2: 1.0, g: 9.81 }, prealloc });
-
-    // Spawn mesh ribbons (comment out particle ribbons to compare)
-    spawn_mesh_ribbon(&mut commands, &mut meshes, &mut materials, "bob1_mesh_ribbon".to_string(), MeshRibbonParams {
+    // One RK4 step per fixed step, simulated time runs at half speed
+    let model = DoublePendulum { m1: 1.0, m2: 1.0, l1: 1.0, l2: 1.0, g: 9.81 };
+    let sim = Simulation::new(
+        Box::new(model.clone()),
+        Box::new(RK4::new(4)),
+        // vec![2.899002795870406, 0.0, 1.913720799888307, 0.0],
+        vec![2.0, 0.0, 2.0, 0.0],
+        time_fixed.timestep().as_secs_f32() / 2.0,
+    );
+    commands.insert_resource(PendulumState { params: model.clone(), sim });
+
+    // Scene root scaling simulation units to pixels, the second bob hangs from the first
+    let scene = commands.spawn((Transform::from_scale(Vec3::splat(RENDER_SCALE)), Name::new("pendulum_scene"))).id();
+    let bob1 = commands.spawn((Bob(0), Transform::default(), ChildOf(scene), Name::new("bob1"))).id();
+    let bob2 = commands.spawn((Bob(1), Transform::default(), ChildOf(bob1), Name::new("bob2"))).id();
+
+    // Spawn mesh ribbons following the bobs (comment out particle ribbons to compare)
+    let ribbon1 = spawn_mesh_ribbon(&mut commands, &mut meshes, &mut materials, "bob1_mesh_ribbon".to_string(), MeshRibbonParams {
         width: 3.0,
         max_points: 1000,
+        lifetime: 4.0,
         color: Color::linear_rgba(10.0, 8.7, 10.0, 1.0),
         fade_to_transparent: true,
         ..Default::default()
     });
-    spawn_mesh_ribbon(&mut commands, &mut meshes, &mut materials, "bob2_mesh_ribbon".to_string(), MeshRibbonParams {
+    let ribbon2 = spawn_mesh_ribbon(&mut commands, &mut meshes, &mut materials, "bob2_mesh_ribbon".to_string(), MeshRibbonParams {
         width: 3.0,
         max_points: 1000,
+        lifetime: 4.0,
         color: Color::linear_rgba(10.0, 8.7, 10.0, 1.0),
         fade_to_transparent: true,
         ..Default::default()
     });
+    commands.entity(ribbon1).insert(RibbonTarget(bob1));
+    commands.entity(ribbon2).insert(RibbonTarget(bob2));
 
     let mut sprite = Sprite::from_image(asset_server.load("double-pendulum.png"));
     sprite.color = Color::Srgba(Srgba { red: 1.5, green: 1.5, blue: 1.5, alpha: 1.0 });
@@ -166,13 +139,14 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
     ));
 
     // Spawn graph widget to track energy or position
-    spawn_graph_widget(&mut commands, GraphParams {
-        position: Vec2::new(-600.0, 320.0),
-        size: Vec2::new(250.0, 150.0),
-        max_points: 600,
+    let bob2_y = spawn_graph_widget(&mut commands, GraphParams {
+        placement: Some(GraphPlacement::new(GraphAnchor::TopLeft)),
+        // A long history, decimated per pixel column and drawn as one mesh
+        max_points: 6000,
+        backend: GraphBackend::Mesh,
         line_color: Color::linear_rgba(3.0, 0.6, 0.2, 1.0),
         label: "Bob2 Y-Position".to_string(),
-        x_gridlines: GridlineConfig::Fixed { spacing: 4.0 },
+        x_gridlines: GridlineConfig::Fixed { spacing: 10.0 },
         y_gridlines: GridlineConfig::Dynamic {
             min_spacing: 20.0,
             num_lines: 4,
@@ -183,11 +157,14 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
         font_size: 14.0,
         ..Default::default()
     });
+    commands.entity(bob2_y).insert(Probe::new(|state: &PendulumState| {
+        let (bob1_pos, bob2_pos) = state.bob_offsets();
+        -(bob1_pos.y + bob2_pos.y) * RENDER_SCALE
+    }));
 
     // Spawn state space plot (KE vs PE)
-    spawn_graph_widget(&mut commands, GraphParams {
-        position: Vec2::new(350.0, 320.0),
-        size: Vec2::new(250.0, 150.0),
+    let potential = spawn_graph_widget(&mut commands, GraphParams {
+        placement: Some(GraphPlacement::new(GraphAnchor::TopRight)),
         max_points: 200,
         line_color: Color::linear_rgba(0.2, 3.0, 0.6, 1.0),
         grid_color: Color::srgba(0.5, 0.5, 0.5, 0.3),
@@ -207,33 +184,102 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
         font_size: 14.0,
         ..Default::default()
     });
+    commands.entity(potential).insert(
+        Probe::new(|state: &PendulumState| state.potential_energy().1)
+            .with_x(|state: &PendulumState| state.potential_energy().0),
+    );
+
+    // Kinetic energy of each bob and the total energy on shared axes
+    let energy = spawn_multi_series_graph_widget(&mut commands, GraphParams {
+        placement: Some(GraphPlacement::new(GraphAnchor::CenterLeft)),
+        max_points: 600,
+        label: "Energy (J)".to_string(),
+        x_title: "t (s)".to_string(),
+        x_gridlines: GridlineConfig::Fixed { spacing: 4.0 },
+        y_gridlines: GridlineConfig::Dynamic {
+            min_spacing: 5.0,
+            num_lines: 4,
+        },
+        gridline_origin: Vec2::ZERO,
+        font_size: 14.0,
+        ..Default::default()
+    }, vec![
+        GraphSeries::new("KE1", Color::linear_rgba(3.0, 0.6, 0.2, 1.0)),
+        GraphSeries::new("KE2", Color::linear_rgba(0.2, 0.6, 3.0, 1.0))
+            .with_style(SeriesStyle::Dashed { dash: 6.0, gap: 4.0 }),
+        GraphSeries::new("Total", Color::linear_rgba(0.2, 3.0, 0.6, 1.0))
+            .with_style(SeriesStyle::Points { radius: 1.0 }),
+    ]);
+    commands.entity(energy).insert(
+        Probe::new(|state: &PendulumState| state.kinetic_energy().0)
+            .with(|state: &PendulumState| state.kinetic_energy().1)
+            .with(|state: &PendulumState| {
+                let (ke, pe) = (state.kinetic_energy(), state.potential_energy());
+                ke.0 + ke.1 + pe.0 + pe.1
+            }),
+    );
+
+    // Spectrum of bob2's horizontal position (broadband when chaotic)
+    let spectrum = spawn_spectrum_widget(&mut commands, SpectrumParams {
+        graph: GraphParams {
+            placement: Some(GraphPlacement::new(GraphAnchor::BottomLeft)),
+            label: "Bob2 X spectrum (dB)".to_string(),
+            font_size: 14.0,
+            ..SpectrumParams::default().graph
+        },
+        // One sample per fixed step, simulated time runs at half speed
+        sample_rate: 2.0 / time_fixed.timestep().as_secs_f32(),
+        downsample: 8,
+        ..Default::default()
+    });
+    commands.entity(spectrum).insert(Probe::new(|state: &PendulumState| {
+        state.params.l1 * state.theta1().sin() + state.params.l2 * state.theta2().sin()
+    }));
+
+    // Relative energy drift, shows the integrator quality
+    spawn_invariant_monitor(
+        &mut commands,
+        Box::new(model),
+        0,
+        GraphParams {
+            placement: Some(GraphPlacement::new(GraphAnchor::BottomRight)),
+            max_points: 600,
+            line_color: Color::linear_rgba(0.6, 0.2, 3.0, 1.0),
+            label: "Energy drift (%)".to_string(),
+            x_title: "t (s)".to_string(),
+            x_gridlines: GridlineConfig::Fixed { spacing: 4.0 },
+            y_gridlines: GridlineConfig::Dynamic {
+                min_spacing: 0.01,
+                num_lines: 4,
+            },
+            // Fits the drift again once a transient has scrolled out
+            y_range: RangeMode::Autoscale { shrink_below: 0.5 },
+            min_y_range: 1e-4,
+            symmetric_y: true,
+            font_size: 14.0,
+            ..Default::default()
+        },
+    );
 }
 
 
 fn step_pendulum(time_fixed: Res<Time<Fixed>>, mut state: ResMut<PendulumState>) {
-    let dt = time_fixed.delta_secs() / 2.0;
-    let t = time_fixed.elapsed_secs() / 2.0;
-
-    // let y0 = vec![state.theta1, state.omega1, state.theta2, state.omega2];
-    state.prealloc.y0[0] = state.theta1;
-    state.prealloc.y0[1] = state.omega1;
-    state.prealloc.y0[2] = state.theta2;
-    state.prealloc.y0[3] = state.omega2;
-
-    rk4::rk4(t, dt, &mut state.prealloc);
-    state.theta1 = state.prealloc.out[0];
-    state.omega1 = state.prealloc.out[1];
-    state.theta2 = state.prealloc.out[2];
-    state.omega2 = state.prealloc.out[3];
+    state.sim.advance(time_fixed.delta_secs() / 2.0);
+}
+
+// Move the bob entities followed by the ribbons, sampled at the end of the fixed step
+fn move_bobs(state: Res<PendulumState>, mut q_bob: Query<(&mut Transform, &Bob)>) {
+    let (bob1_pos, bob2_pos) = state.bob_offsets();
+    for (mut transform, bob) in q_bob.iter_mut() {
+        transform.translation = if bob.0 == 0 { bob1_pos } else { bob2_pos };
+    }
 }
 
 
 fn draw_pendulum(
     mut painter: ShapePainter,
+    mut figure: ResMut<FigureCapture>,
     state: Res<PendulumState>,
-    mut q_mesh: Query<(&mut PhyzViz::utils::mesh_ribbon::MeshRibbon, &Name)>,
-    mut q_graph: Query<&mut PhyzViz::utils::graph::GraphWidget>,
-    time_fixed: Res<Time<Fixed>>,
 ) {
     painter.scale(Vec3::splat(RENDER_SCALE));
 
@@ -242,16 +288,10 @@ fn draw_pendulum(
     // 2) pivot circle (z = +0.001)
     // 3) bob circle (z = +0.002)
 
-    let length1: f32 = 2.0;
-    let length2: f32 = 2.0;
     let bob_radius = 0.12;
     
     let pivot = Vec3::ZERO;
-    let theta1 = state.theta1;
-    let bob1_pos = Vec3::new(length1 * theta1.sin(), -length1 * theta1.cos(), 0.0);
-
-    let theta2 = state.theta2;
-    let bob2_pos = Vec3::new(length2 * theta2.sin(), -length2 * theta2.cos(), 0.0);
+    let (bob1_pos, bob2_pos) = state.bob_offsets();
 
     // Save base transform
     let base = painter.transform;
@@ -261,6 +301,7 @@ fn draw_pendulum(
     painter.thickness = 0.05;
     painter.set_color(Srgba { red: 4.0 * 165.0 / 255.0, green: 4.0 * 136.0 / 255.0, blue: 4.0 * 94.0 / 255.0, alpha: 1.0 });
     painter.line(pivot, bob1_pos);
+    figure.line(&painter, pivot, bob1_pos);
 
     // --- pivot circle at z = +0.001 ---
     let mut t = base;
@@ -271,6 +312,7 @@ fn draw_pendulum(
     painter.set_color(Srgba { red: 4.0 * 165.0 / 255.0, green: 4.0 * 136.0 / 255.0, blue: 4.0 * 94.0 / 255.0, alpha: 1.0 });
     painter.translate(pivot);
     painter.circle(0.07);
+    figure.circle(&painter, 0.07);
 
     // --- bob circle at z = +0.002 ---
     let mut t2 = base;
@@ -279,12 +321,14 @@ fn draw_pendulum(
     painter.translate(bob1_pos);
     painter.set_color(Color::linear_rgba(3.0, 0.6, 0.2, 1.0)); // bright for bloom
     painter.circle(bob_radius);
+    figure.circle(&painter, bob_radius);
 
     // --- rod 2 at z = 0.0 ---
     painter.transform = base;
     painter.thickness = 0.03;
     painter.set_color(Srgba { red: 4.0 * 165.0 / 255.0, green: 4.0 * 136.0 / 255.0, blue: 4.0 * 94.0 / 255.0, alpha: 1.0 });
     painter.line(bob1_pos, bob1_pos + bob2_pos);
+    figure.line(&painter, bob1_pos, bob1_pos + bob2_pos);
 
     // --- bob circle 2 at z = +0.002 ---
     let mut t3 = base;
@@ -293,36 +337,10 @@ fn draw_pendulum(
     painter.translate(bob1_pos + bob2_pos);
     painter.set_color(Color::linear_rgba(3.0, 0.6, 0.2, 1.0)); // bright for bloom
     painter.circle(bob_radius);
+    figure.circle(&painter, bob_radius);
 
     // (optional) restore
     painter.transform = base;
-
-    // Move mesh ribbon positions (update current_position field)
-    for (mut ribbon, name) in q_mesh.iter_mut() {
-        let bob_pos = if name.as_str() == "bob1_mesh_ribbon" {
-            bob1_pos * RENDER_SCALE
-        } else {
-            (bob1_pos + bob2_pos) * RENDER_SCALE
-        };
-        ribbon.current_position = bob_pos;
-    }
-
-    // Update graphs
-    let bob2_y = -(bob1_pos.y + bob2_pos.y) * RENDER_SCALE;
-    let ke = state.params.kinetic_energy(state.theta1, state.omega1, state.theta2, state.omega2);
-    let pe = state.params.potential_energy(state.theta1, state.theta2);
-
-    let mut graph_iter = q_graph.iter_mut();
-    
-    // First graph: bob2 y-position vs time
-    if let Some(mut graph) = graph_iter.next() {
-        graph.add_point(time_fixed.elapsed_secs(), bob2_y);
-    }
-    
-    // Second graph: state space (KE vs PE)
-    if let Some(mut graph) = graph_iter.next() {
-        graph.add_point(pe.0, pe.1);
-    }
 }
 
 fn main() {
@@ -350,14 +368,21 @@ fn main() {
             .set(TimePlugin::default()),
         )
         .add_plugins(Shape2dPlugin::default())
+        .add_plugins(MeshRibbonPlugin)
+        .add_plugins(GraphOverlayPlugin)
+        .add_plugins(GraphInteractionPlugin)
+        .add_plugins(GraphExportPlugin)
+        .add_plugins(FigureExportPlugin)
+        // Simulated time runs at half speed
+        .insert_resource(RibbonClock::new(0.5))
         .insert_resource(ClearColor(bevy::prelude::Color::Srgba(Srgba { red: 84.0 / 255.0, green: 18.0 / 255.0, blue: 18.0 / 255.0, alpha: 1.0 })))
         .add_systems(Startup, setup )
         // Physics on a fixed timestep
-        .add_systems(FixedUpdate, step_pendulum)
+        .add_systems(FixedUpdate, (step_pendulum, move_bobs, probe_graphs::<PendulumState>, monitor_invariants::<PendulumState>).chain())
         // Rendering on the variable-rate Update schedule (interpolation optional)
         .add_systems(Update, draw_pendulum)
-        .add_systems(Update, add_ribbon_position)
-        .add_systems(Update, draw_graph_widget);
+        .add_systems(Update, draw_graph_widget)
+        .add_systems(Update, draw_spectrum_widget);
 
     #[cfg(feature = "fps_overlay")]
     app.add_plugins(FpsOverlayPlugin::default());
@@ -5,94 +5,121 @@ use bevy::time::{Fixed, TimePlugin};
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use std::time::Duration;
 
-use PhyzViz::utils::ODEs;
-use PhyzViz::utils::rk4;
-use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, add_ribbon_position};
+use PhyzViz::models::lorenz::Lorenz;
+use PhyzViz::utils::rk4::RK4;
+use PhyzViz::utils::simulation::Simulation;
+use PhyzViz::utils::colormap::Colormap;
+use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon_3d, Decimation, GradientAxis, MeshRibbonParams, MeshRibbonPlugin, RibbonClock, RibbonMaterial3d, RibbonTarget};
+use PhyzViz::utils::figure::FigureExportPlugin;
+use PhyzViz::utils::orbit_camera::{orbit_camera, OrbitCamera};
+use PhyzViz::utils::recurrence::{spawn_recurrence_plot, RecurrenceParams, RecurrencePlot, update_recurrence_plot};
+use PhyzViz::utils::graph::GraphParams;
+use PhyzViz::utils::graph_overlay::{GraphAnchor, GraphOverlayPlugin, GraphPlacement};
+use PhyzViz::utils::histogram::{spawn_histogram_widget, draw_histogram_widget, HistogramParams, KdeParams};
+use PhyzViz::utils::probe::{probe_graphs, Observables, Probe};
+use bevy_vector_shapes::prelude::*;
 use bevy::{
     core_pipeline::tonemapping::{DebandDither, Tonemapping},
     post_process::bloom::Bloom,
+    render::view::Hdr,
 };
 
 // Render and ribbon params
 const RENDER_SCALE: f32 = 10.0;
 const RIBBON_WIDTH: f32 = 5.0;
-const RIBBON_MAX_POINTS: usize = 20000;
+const RIBBON_MAX_POINTS: usize = 120_000;
+/// Simulated seconds of trail, ~115k samples at 120 Hz and quarter speed
+const RIBBON_LIFETIME: f32 = 240.0;
 
-// Lorenz system
-pub struct Lorenz {
-    pub sigma: f32,
-    pub rho: f32,
-    pub beta: f32,
-}
+/// Point moving along the attractor, followed by the ribbon
+#[derive(Component)]
+struct Tracer;
 
 #[derive(Resource)]
 struct LorenzState {
-    x: f32,
-    y: f32,
-    z: f32,
-    params: Lorenz,
-    prealloc: rk4::RK4Prealloc,
+    sim: Simulation, // State vector is [x, y, z]
 }
 
-impl ODEs::ODEFunc for Lorenz {
-    fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
-        let x = y[0];
-        let z = y[2];
-        let dy = y[1];
+impl LorenzState {
+    fn position(&self) -> Vec3 {
+        let y = self.sim.state();
+        Vec3::new(y[0], y[1], y[2])
+    }
 
-        let dxdt = self.sigma * (dy - x);
-        let dydt = x * (self.rho - z) - dy;
-        let dzdt = x * dy - self.beta * z;
+    /// Position in the 3D scene: Lorenz z is up (Bevy Y) and the attractor is centered on the origin
+    fn world_position(&self) -> Vec3 {
+        let p = self.position();
+        Vec3::new(p.x, p.z - 25.0, -p.y) * RENDER_SCALE
+    }
+}
 
-        out[0] = dxdt;
-        out[1] = dydt;
-        out[2] = dzdt;
+impl Observables for LorenzState {
+    fn observable(&self, name: &str) -> Option<f32> {
+        let p = self.position();
+        match name {
+            "x" => Some(p.x),
+            "y" => Some(p.y),
+            "z" => Some(p.z),
+            _ => None,
+        }
     }
 }
 
-fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>) {
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<RibbonMaterial3d>>, mut images: ResMut<Assets<Image>>, time_fixed: Res<Time<Fixed>>) {
+    // Orbiting 3D camera for the attractor
+    let orbit = OrbitCamera {
+        radius: 900.0,
+        yaw: 0.6,
+        pitch: 0.25,
+        min_radius: 50.0,
+        max_radius: 5000.0,
+        ..default()
+    };
     commands.spawn((
-        Camera2d,
+        Camera3d::default(),
         Camera {
             clear_color: ClearColorConfig::Custom(Color::BLACK),
             ..default()
         },
+        orbit.transform(),
+        orbit,
         Tonemapping::TonyMcMapface, // 1. Using a tonemapper that desaturates to white is recommended
         Bloom::default(),           // 2. Enable bloom for the camera
         DebandDither::Enabled,      // Optional: bloom causes gradients which cause banding
     ));
 
-    let prealloc = rk4::RK4Prealloc {
-        y0: vec![0.0; 3],
-        k1: vec![0.0; 3],
-        k2: vec![0.0; 3],
-        k3: vec![0.0; 3],
-        k4: vec![0.0; 3],
-        out: vec![0.0; 3],
-        func: Box::new(Lorenz {
-            sigma: 10.0,
-            rho: 28.0,
-            beta: 8.0 / 3.0,
-        }),
-    };
+    // 2D overlay for the recurrence plot, drawn after the 3D camera without clearing it.
+    // Same HDR target as the 3D camera, which already tonemapped it.
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        Hdr,
+        Tonemapping::None,
+    ));
 
-    // Lorenz initial state
-    commands.insert_resource(LorenzState {
-        x: 10.0,
-        y: 10.0,
-        z: 10.0,
-        params: Lorenz {
+    // Lorenz initial state, one RK4 step per fixed step with simulated time at quarter speed
+    let sim = Simulation::new(
+        Box::new(Lorenz {
             sigma: 10.0,
             rho: 28.0,
             beta: 8.0 / 3.0,
-        },
-        prealloc,
-    });
+        }),
+        Box::new(RK4::new(3)),
+        vec![10.0, 10.0, 10.0],
+        time_fixed.timestep().as_secs_f32() / 4.0,
+    );
+    commands.insert_resource(LorenzState { sim });
 
     let scale = 2.0;
 
+    let tracer = commands.spawn((Tracer, Transform::default(), Name::new("lorenz_tracer"))).id();
+
     // Spawn mesh ribbon for the tracer
-    spawn_mesh_ribbon(
+    let ribbon = spawn_mesh_ribbon_3d(
         &mut commands,
         &mut meshes,
         &mut materials,
@@ -100,34 +127,85 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
         MeshRibbonParams {
             width: RIBBON_WIDTH,
             max_points: RIBBON_MAX_POINTS,
-            color: Color::linear_rgba(scale * 1.8, scale * 1.4, scale * 3.0, 1.0),
+            lifetime: RIBBON_LIFETIME,
+            // Older loops are simplified to half a pixel, about one sample in six is drawn
+            decimation: Some(Decimation {
+                tolerance: 0.5,
+                recent: 1024,
+                max_drawn: 30_000,
+            }),
+            // Samples are already dense at 120 Hz, skip the spline resampling
+            subdivisions: 1,
+            color: Color::linear_rgba(1.8, 1.4, 3.0, 1.0),
             fade_to_transparent: true,
             width_variation: PhyzViz::utils::mesh_ribbon::InterpolationType::Poly(0.2),
             transparency_variance: PhyzViz::utils::mesh_ribbon::InterpolationType::Poly(0.2),
+            color_variation: PhyzViz::utils::mesh_ribbon::InterpolationType::Smoothstep,
+            // Cools down from the head to the tail
+            colormap: Colormap::new(vec![
+                Color::WHITE,
+                Color::linear_rgb(0.6, 0.5, 1.0),
+                Color::linear_rgb(0.3, 0.15, 0.6),
+            ]),
+            gradient_axis: GradientAxis::Age,
+            glow: scale,
+            ..Default::default()
         }
     );
+    commands.entity(ribbon).insert(RibbonTarget(tracer));
+
+    // Recurrence plot of the (x, y, z) state, ~6.7 time units of history
+    spawn_recurrence_plot(
+        &mut commands,
+        &mut images,
+        RecurrenceParams {
+            position: Vec2::new(350.0, 320.0),
+            size: Vec2::new(250.0, 250.0),
+            window: 400,
+            threshold: 4.0,
+            downsample: 8,
+            label: "Recurrence plot (eps = 4)".to_string(),
+            font_size: 14.0,
+            ..Default::default()
+        },
+    );
+
+    // Distribution of z along the attractor, its invariant measure projected on z
+    let histogram = spawn_histogram_widget(&mut commands, HistogramParams {
+        graph: GraphParams {
+            placement: Some(GraphPlacement::new(GraphAnchor::TopLeft)),
+            line_color: Color::linear_rgba(1.8, 1.4, 3.0, 1.0),
+            label: "z distribution".to_string(),
+            x_title: "z".to_string(),
+            font_size: 14.0,
+            ..HistogramParams::default().graph
+        },
+        bins: 48,
+        kde: Some(KdeParams {
+            color: Color::linear_rgba(3.0, 0.6, 0.2, 1.0),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    commands.entity(histogram).insert(Probe::<LorenzState>::named("z"));
 }
 
 // Integrate Lorenz at a fixed timestep
 fn step_lorenz(time_fixed: Res<Time<Fixed>>, mut state: ResMut<LorenzState>) {
-    let dt = time_fixed.delta_secs() / 4.0;
-    let t = time_fixed.elapsed_secs() / 4.0;
-
-    state.prealloc.y0[0] = state.x;
-    state.prealloc.y0[1] = state.y;
-    state.prealloc.y0[2] = state.z;
-    rk4::rk4(t, dt, &mut state.prealloc);
+    state.sim.advance(time_fixed.delta_secs() / 4.0);
+}
 
-    state.x = state.prealloc.out[0];
-    state.y = state.prealloc.out[1];
-    state.z = state.prealloc.out[2];
+// Feed the recurrence plot with the state after each step
+fn sample_recurrence(mut q_plot: Query<&mut RecurrencePlot>, state: Res<LorenzState>) {
+    for mut plot in q_plot.iter_mut() {
+        plot.push_state(state.sim.state());
+    }
 }
 
-// Update the ribbon position to the current Lorenz position
-fn update_ribbon(mut q_mesh: Query<&mut PhyzViz::utils::mesh_ribbon::MeshRibbon>, state: Res<LorenzState>) {
-    if let Ok(mut ribbon) = q_mesh.single_mut() {
-        let pos = Vec3::new(state.x, state.y, state.z) * RENDER_SCALE;
-        ribbon.current_position = pos;
+// Move the tracer to the current Lorenz position, sampled at the end of the fixed step
+fn move_tracer(mut q_tracer: Query<&mut Transform, With<Tracer>>, state: Res<LorenzState>) {
+    if let Ok(mut transform) = q_tracer.single_mut() {
+        transform.translation = state.world_position();
     }
 }
 
@@ -149,12 +227,18 @@ fn main() {
         )
         // Fixed step (e.g., 120 Hz)
         .insert_resource(Time::<Fixed>::from_duration(Duration::from_secs_f64(1.0 / 120.0)))
+        .add_plugins(Shape2dPlugin::default())
+        .add_plugins(MeshRibbonPlugin)
+        .add_plugins(FigureExportPlugin)
+        .add_plugins(GraphOverlayPlugin)
+        // Simulated time runs at quarter speed
+        .insert_resource(RibbonClock::new(0.25))
         // .add_plugins(FrameTimeDiagnosticsPlugin::default())
         .insert_resource(ClearColor(Color::BLACK))
         .add_systems(Startup, setup)
-        .add_systems(FixedUpdate, step_lorenz)
-        .add_systems(Update, update_ribbon)
-        .add_systems(Update, add_ribbon_position);
+        .add_systems(FixedUpdate, (step_lorenz, move_tracer, sample_recurrence, probe_graphs::<LorenzState>).chain())
+        .add_systems(Update, orbit_camera)
+        .add_systems(Update, (update_recurrence_plot, draw_histogram_widget));
 
     #[cfg(feature = "fps_overlay")]
     app.add_plugins(FrameTimeDiagnosticsPlugin::default());
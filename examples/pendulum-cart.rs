@@ -6,8 +6,13 @@ use std::time::Duration;
 
 use rapier2d_f64::prelude::*;
 
-use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, add_ribbon_position};
+use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, MeshRibbonPlugin, RibbonMaterial, RibbonTarget};
 use PhyzViz::utils::graph::{spawn_graph_widget, GraphParams, GridlineConfig, draw_graph_widget};
+use PhyzViz::utils::graph_export::GraphExportPlugin;
+use PhyzViz::utils::graph_interaction::GraphInteractionPlugin;
+use PhyzViz::utils::graph_overlay::{GraphAnchor, GraphOverlayPlugin, GraphPlacement};
+use PhyzViz::utils::invariants::{spawn_invariant_monitor, monitor_invariants, Invariant, SimulationState};
+use PhyzViz::utils::probe::{probe_graphs, Observables, Probe};
 use bevy::{
     core_pipeline::tonemapping::{DebandDither, Tonemapping},
     post_process::bloom::Bloom,
@@ -33,6 +38,34 @@ const PENDULUM_LENGTH: f64 = 2.0;
 const GRAVITY: f64 = 9.81;
 const INITIAL_ANGLE: f64 = 11.0 * std::f64::consts::PI / 12.0; // Initial angle in radians (0 = hanging down, positive = right)
 
+/// Conserved quantities of the cart-pendulum, evaluated on the state exposed by `PhysicsWorld`:
+/// the cart and pendulum positions then their velocities, `[cart_x, cart_y, pendulum_x, pendulum_y,
+/// cart_vx, cart_vy, pendulum_vx, pendulum_vy]`
+struct CartPendulumInvariants;
+
+impl Invariant for CartPendulumInvariants {
+    fn invariant_names(&self) -> Vec<&'static str> {
+        vec!["Energy", "Horizontal momentum"]
+    }
+
+    fn invariants(&self, y: &[f32], out: &mut Vec<f32>) {
+        let (pendulum_y, cart_velocity, pendulum_velocity) = (y[3], Vec2::new(y[4], y[5]), Vec2::new(y[6], y[7]));
+        let cart_mass = CART_MASS as f32;
+        let pendulum_mass = PENDULUM_MASS as f32;
+
+        // Potential energy taking cart level as zero reference
+        let energy = 0.5 * cart_mass * cart_velocity.length_squared()
+            + 0.5 * pendulum_mass * pendulum_velocity.length_squared()
+            + pendulum_mass * GRAVITY as f32 * pendulum_y;
+        // No horizontal external force acts on the system
+        let momentum = cart_mass * cart_velocity.x + pendulum_mass * pendulum_velocity.x;
+
+        out.clear();
+        out.push(energy);
+        out.push(momentum);
+    }
+}
+
 #[derive(Resource)]
 struct PhysicsWorld {
     rigid_body_set: RigidBodySet,
@@ -47,6 +80,8 @@ struct PhysicsWorld {
     ccd_solver: CCDSolver,
     cart_handle: RigidBodyHandle,
     pendulum_handle: RigidBodyHandle,
+    /// Simulated time
+    time: f64,
 }
 
 impl PhysicsWorld {
@@ -109,6 +144,7 @@ impl PhysicsWorld {
             ccd_solver: CCDSolver::new(),
             cart_handle,
             pendulum_handle,
+            time: 0.0,
         }
     }
 
@@ -118,20 +154,22 @@ impl PhysicsWorld {
         let event_handler = ();
 
         for _ in 0..(BEVY_FIXED_TIME_STEP / INTEGRATION_TIME_STEP) as usize {
-        self.physics_pipeline.step(
-            &gravity,
-            &self.integration_parameters,
-            &mut self.island_manager,
-            &mut self.broad_phase,
-            &mut self.narrow_phase,
-            &mut self.rigid_body_set,
-            &mut self.collider_set,
-            &mut self.impulse_joint_set,
-            &mut self.multibody_joint_set,
-            &mut self.ccd_solver,
-            &physics_hooks,
-            &event_handler,
-        );}
+            self.physics_pipeline.step(
+                &gravity,
+                &self.integration_parameters,
+                &mut self.island_manager,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.rigid_body_set,
+                &mut self.collider_set,
+                &mut self.impulse_joint_set,
+                &mut self.multibody_joint_set,
+                &mut self.ccd_solver,
+                &physics_hooks,
+                &event_handler,
+            );
+            self.time += self.integration_parameters.dt;
+        }
     }
 
     fn cart_position(&self) -> Vector<f64> {
@@ -150,23 +188,6 @@ impl PhysicsWorld {
         self.rigid_body_set[self.pendulum_handle].linvel().clone()
     }
 
-    fn total_energy(&self) -> (f64, f64) {
-        let cart = &self.rigid_body_set[self.cart_handle];
-        let pendulum = &self.rigid_body_set[self.pendulum_handle];
-
-        // Kinetic energy
-        let cart_ke = 0.5 * CART_MASS * cart.linvel().norm_squared();
-        let pendulum_ke = 0.5 * PENDULUM_MASS * pendulum.linvel().norm_squared();
-        let total_ke = cart_ke + pendulum_ke;
-
-        // Potential energy (taking cart level as zero reference)
-        let cart_pe = 0.0;
-        let pendulum_pe = PENDULUM_MASS * GRAVITY * pendulum.translation().y;
-        let total_pe = cart_pe + pendulum_pe;
-
-        (total_ke, total_pe)
-    }
-
     fn pendulum_angle(&self) -> f64 {
         let cart_pos = self.cart_position();
         let pendulum_pos = self.pendulum_position();
@@ -183,10 +204,30 @@ impl PhysicsWorld {
     }
 }
 
+impl SimulationState for PhysicsWorld {
+    fn state(&self) -> Vec<f32> {
+        let (cart, pendulum) = (self.cart_position(), self.pendulum_position());
+        let (cart_velocity, pendulum_velocity) = (self.cart_velocity(), self.pendulum_velocity());
+        [cart.x, cart.y, pendulum.x, pendulum.y, cart_velocity.x, cart_velocity.y, pendulum_velocity.x, pendulum_velocity.y]
+            .map(|value| value as f32)
+            .to_vec()
+    }
+
+    fn time(&self) -> f32 {
+        self.time as f32
+    }
+}
+
+impl Observables for PhysicsWorld {}
+
+/// Pendulum bob followed by the trail, positioned in physics units under the scaled scene root
+#[derive(Component)]
+struct PendulumBob;
+
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut materials: ResMut<Assets<RibbonMaterial>>,
 ) {
     commands.spawn((
         Camera2d,
@@ -200,8 +241,12 @@ fn setup(
     
     commands.insert_resource(physics);
 
+    // Scene root scaling physics units to pixels, the trail samples the bob's world position
+    let scene = commands.spawn((Transform::from_scale(Vec3::splat(RENDER_SCALE)), Name::new("cart_scene"))).id();
+    let bob = commands.spawn((PendulumBob, Transform::default(), ChildOf(scene), Name::new("pendulum_bob"))).id();
+
     // Spawn mesh ribbon for pendulum trail
-    spawn_mesh_ribbon(
+    let trail = spawn_mesh_ribbon(
         &mut commands,
         &mut meshes,
         &mut materials,
@@ -209,17 +254,18 @@ fn setup(
         MeshRibbonParams {
             width: 10.0,
             max_points: 200,
+            lifetime: 1.6,
             color: Color::linear_rgba(0.4, 1.362, 1.995, 1.0), // Lighter than clear color
             fade_to_transparent: true,
             width_variation: PhyzViz::utils::mesh_ribbon::InterpolationType::Poly(10.0),
             ..Default::default()
         },
     );
+    commands.entity(trail).insert(RibbonTarget(bob));
     
     // Graph for cart position
-    spawn_graph_widget(&mut commands, GraphParams {
-        position: Vec2::new(-600.0, 320.0),
-        size: Vec2::new(250.0, 150.0),
+    let cart_x = spawn_graph_widget(&mut commands, GraphParams {
+        placement: Some(GraphPlacement::new(GraphAnchor::TopLeft)),
         max_points: 600,
         line_color: Color::linear_rgba(0.2, 0.6, 3.0, 1.0),
         label: "Cart X-Position".to_string(),
@@ -234,15 +280,17 @@ fn setup(
         font_size: 14.0,
         ..Default::default()
     });
+    commands.entity(cart_x).insert(Probe::new(|physics: &PhysicsWorld| physics.cart_position().x as f32 * RENDER_SCALE));
 
     // Graph for pendulum angle vs time
-    spawn_graph_widget(&mut commands, GraphParams {
-        position: Vec2::new(350.0, 320.0),
-        size: Vec2::new(250.0, 150.0),
+    let angle = spawn_graph_widget(&mut commands, GraphParams {
+        placement: Some(GraphPlacement::new(GraphAnchor::TopRight)),
         max_points: 600,
         line_color: Color::linear_rgba(3.0, 0.6, 0.2, 1.0),
         grid_color: Color::srgba(0.5, 0.5, 0.5, 0.3),
         label: "Pendulum Angle".to_string(),
+        x_title: "t (s)".to_string(),
+        symmetric_y: true,
         x_gridlines: GridlineConfig::Fixed { spacing: 4.0 },
         y_gridlines: GridlineConfig::Dynamic {
             min_spacing: 20.0,
@@ -255,18 +303,47 @@ fn setup(
         font_size: 14.0,
         ..Default::default()
     });
+    commands.entity(angle).insert(Probe::new(|physics: &PhysicsWorld| physics.pendulum_angle() as f32 * RENDER_SCALE));
+
+    // Relative drift of the conserved quantities (joint constraint solver quality)
+    for (index, (label, anchor)) in [("Energy drift (%)", GraphAnchor::BottomLeft), ("Momentum drift (%)", GraphAnchor::BottomRight)].into_iter().enumerate() {
+        spawn_invariant_monitor(
+            &mut commands,
+            Box::new(CartPendulumInvariants),
+            index,
+            GraphParams {
+                placement: Some(GraphPlacement::new(anchor)),
+                max_points: 600,
+                line_color: Color::linear_rgba(3.0, 0.6, 0.2, 1.0),
+                label: label.to_string(),
+                x_gridlines: GridlineConfig::Fixed { spacing: 4.0 },
+                y_gridlines: GridlineConfig::Dynamic {
+                    min_spacing: 0.01,
+                    num_lines: 4,
+                },
+                min_y_range: 1e-4,
+                font_size: 14.0,
+                ..Default::default()
+            },
+        );
+    }
 }
 
 fn step_physics(mut physics: ResMut<PhysicsWorld>) {
     physics.step();
 }
 
+// Move the bob entity followed by the trail, sampled at the end of the fixed step
+fn move_bob(physics: Res<PhysicsWorld>, mut q_bob: Query<&mut Transform, With<PendulumBob>>) {
+    let pendulum_pos = physics.pendulum_position();
+    if let Ok(mut transform) = q_bob.single_mut() {
+        transform.translation = Vec3::new(pendulum_pos.x as f32, pendulum_pos.y as f32, 0.0);
+    }
+}
+
 fn draw_system(
     mut painter: ShapePainter,
     physics: Res<PhysicsWorld>,
-    mut q_mesh: Query<&mut PhyzViz::utils::mesh_ribbon::MeshRibbon>,
-    mut q_graph: Query<&mut PhyzViz::utils::graph::GraphWidget>,
-    time_fixed: Res<Time<Fixed>>,
 ) {
     painter.scale(Vec3::splat(RENDER_SCALE));
 
@@ -337,25 +414,6 @@ fn draw_system(
     painter.circle(0.12);
 
     painter.transform = base;
-
-    // Update mesh ribbon
-    if let Ok(mut ribbon) = q_mesh.single_mut() {
-        ribbon.current_position = pendulum_render_pos * RENDER_SCALE;
-    }
-
-    // Update graphs
-    let angle = physics.pendulum_angle();
-    let mut graph_iter = q_graph.iter_mut();
-
-    // First graph: cart x-position vs time
-    if let Some(mut graph) = graph_iter.next() {
-        graph.add_point(time_fixed.elapsed_secs(), cart_pos.x as f32 * RENDER_SCALE);
-    }
-
-    // Second graph: pendulum angle vs time
-    if let Some(mut graph) = graph_iter.next() {
-        graph.add_point(time_fixed.elapsed_secs(), angle as f32 * RENDER_SCALE);
-    }
 }
 
 fn main() {
@@ -378,6 +436,10 @@ fn main() {
             .set(TimePlugin::default()),
     )
     .add_plugins(Shape2dPlugin::default())
+    .add_plugins(MeshRibbonPlugin)
+    .add_plugins(GraphOverlayPlugin)
+    .add_plugins(GraphInteractionPlugin)
+    .add_plugins(GraphExportPlugin)
     .insert_resource(ClearColor(bevy::prelude::Color::Srgba(Srgba {
         red: 0.067,
         green: 0.227,
@@ -385,9 +447,8 @@ fn main() {
         alpha: 1.0,
     })))
     .add_systems(Startup, setup)
-    .add_systems(FixedUpdate, step_physics)
+    .add_systems(FixedUpdate, (step_physics, move_bob, probe_graphs::<PhysicsWorld>, monitor_invariants::<PhysicsWorld>).chain())
     .add_systems(Update, draw_system)
-    .add_systems(Update, add_ribbon_position)
     .add_systems(Update, draw_graph_widget);
 
     #[cfg(feature = "fps_overlay")]
@@ -4,9 +4,20 @@ use bevy::time::{Fixed, TimePlugin};
 use bevy_vector_shapes::prelude::*;
 use std::time::Duration;
 
-use PhyzViz::utils::ODEs;
-use PhyzViz::utils::rk4;
-use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, MeshRibbonParams, add_ribbon_position};
+use PhyzViz::utils::rk4::RK4;
+use PhyzViz::utils::simulation::Simulation;
+use PhyzViz::utils::colorbar::{spawn_colorbar, ColorbarParams};
+use PhyzViz::utils::colormap::Colormap;
+use PhyzViz::utils::mesh_ribbon::{spawn_mesh_ribbon, GradientAxis, MeshRibbonParams, MeshRibbonPlugin, RibbonClock, RibbonMaterial, RibbonTarget, TrailSource};
+use PhyzViz::utils::spectrum::{spawn_spectrum_widget, SpectrumParams, draw_spectrum_widget};
+use PhyzViz::utils::graph::{GraphParams, GridlineConfig, RangeMode, draw_graph_widget};
+use PhyzViz::utils::graph_export::GraphExportPlugin;
+use PhyzViz::utils::graph_interaction::GraphInteractionPlugin;
+use PhyzViz::utils::graph_overlay::{GraphAnchor, GraphOverlayPlugin, GraphPlacement};
+use PhyzViz::utils::figure::{FigureCapture, FigureExportPlugin};
+use PhyzViz::models::pendulum::SimplePendulum;
+use PhyzViz::utils::invariants::{spawn_invariant_monitor, monitor_invariants, SimulationState};
+use PhyzViz::utils::probe::{probe_graphs, Observables, Probe};
 use bevy::{
     core_pipeline::tonemapping::{DebandDither, Tonemapping},
     post_process::bloom::{Bloom},
@@ -16,32 +27,48 @@ use bevy::{
 use bevy::dev_tools::fps_overlay::FpsOverlayPlugin;
 
 const RENDER_SCALE: f32 = 60.0;
-
-struct SimplePendulum {
-    length: f32,
-    gravity: f32,
-}
+const LENGTH: f32 = 2.0;
 
 #[derive(Resource)]
 struct PendulumState {
-    theta: f32,
-    omega: f32,
     params: SimplePendulum,
-    prealloc : rk4::RK4Prealloc,
+    sim: Simulation,   // State vector is [theta, omega]
+}
+
+impl PendulumState {
+    fn theta(&self) -> f32 { self.sim.state()[0] }
+    fn omega(&self) -> f32 { self.sim.state()[1] }
+    fn bob_position(&self) -> Vec3 {
+        let length = self.params.length;
+        Vec3::new(length * self.theta().sin(), -length * self.theta().cos(), 0.0)
+    }
+}
+
+/// Bob followed by the ribbon, positioned in simulation units under the scaled scene root
+#[derive(Component)]
+struct Bob;
+
+impl SimulationState for PendulumState {
+    fn state(&self) -> Vec<f32> {
+        self.sim.state().to_vec()
+    }
+
+    fn time(&self) -> f32 {
+        self.sim.time()
+    }
 }
 
-impl ODEs::ODEFunc for SimplePendulum {
-    fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
-        let theta = y[0];
-        let omega = y[1];
-        let dtheta_dt = omega;
-        let domega_dt = -(self.gravity / self.length) * theta.sin();
-        out[0] = dtheta_dt;
-        out[1] = domega_dt;
+impl Observables for PendulumState {
+    fn observable(&self, name: &str) -> Option<f32> {
+        match name {
+            "theta" => Some(self.theta()),
+            "omega" => Some(self.omega()),
+            _ => None,
+        }
     }
 }
 
-fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<RibbonMaterial>>, mut images: ResMut<Assets<Image>>, asset_server: Res<AssetServer>, time_fixed: Res<Time<Fixed>>) {
     commands.spawn((
         Camera2d,
         Tonemapping::TonyMcMapface,
@@ -49,24 +76,37 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
         DebandDither::Enabled,
     ));
 
-    let prealloc = rk4::RK4Prealloc {
-        y0: vec![0.0, 0.0],
-        k1: vec![0.0, 0.0],
-        k2: vec![0.0, 0.0],
-        k3: vec![0.0, 0.0],
-        k4: vec![0.0, 0.0],
-        out: vec![0.0, 0.0],
-        func: Box::new(SimplePendulum { length: 2.0, gravity: 9.81 }),
-    };
+    // One RK4 step per fixed step, simulated time runs at half speed
+    let model = SimplePendulum { length: LENGTH, gravity: 9.81 };
+    let sim = Simulation::new(
+        Box::new(model.clone()),
+        Box::new(RK4::new(2)),
+        vec![2.5, 0.0],
+        time_fixed.timestep().as_secs_f32() / 2.0,
+    );
+    commands.insert_resource(PendulumState { params: model.clone(), sim });
 
-    commands.insert_resource(PendulumState { theta: 2.5, omega: 0.0, params: SimplePendulum { length: 2.0, gravity: 9.81 }, prealloc });
-
-    // Spawn mesh ribbon
-    spawn_mesh_ribbon(&mut commands, &mut meshes, &mut materials, "bob_mesh_ribbon".to_string(), MeshRibbonParams {
+    // Spawn mesh ribbon, colored by the bob speed
+    let ribbon = spawn_mesh_ribbon(&mut commands, &mut meshes, &mut materials, "bob_mesh_ribbon".to_string(), MeshRibbonParams {
         width: 3.0,
         max_points: 1000,
-        color: Color::linear_rgba(10.0, 8.7, 10.0, 1.0),
+        lifetime: 4.0,
+        color: Color::WHITE,
         fade_to_transparent: true,
+        colormap: Colormap::viridis(),
+        gradient_axis: GradientAxis::Scalar,
+        glow: 6.0,
+        ..Default::default()
+    });
+    // Scene root scaling simulation units to pixels, the ribbon samples the bob's world position
+    let scene = commands.spawn((Transform::from_scale(Vec3::splat(RENDER_SCALE)), Name::new("pendulum_scene"))).id();
+    let bob = commands.spawn((Bob, TrailSource::default(), Transform::default(), ChildOf(scene), Name::new("bob"))).id();
+    commands.entity(ribbon).insert(RibbonTarget(bob));
+
+    spawn_colorbar(&mut commands, &mut images, ribbon, ColorbarParams {
+        position: Vec2::new(540.0, 100.0),
+        label: "Speed (m/s)".to_string(),
+        font_size: 14.0,
         ..Default::default()
     });
 
@@ -78,33 +118,62 @@ fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials
         sprite,
         Transform::from_xyz(0.0, 250.0, -1.0).with_scale(Vec3::splat(0.15)),
     ));
-}
 
-fn step_pendulum(time_fixed: Res<Time<Fixed>>, mut state: ResMut<PendulumState>) {
-    let dt = time_fixed.delta_secs() / 2.0;
-    let t = time_fixed.elapsed_secs() / 2.0;
+    // Spectrum of theta (one sample per fixed step, simulated time runs at half speed)
+    let spectrum = spawn_spectrum_widget(&mut commands, SpectrumParams {
+        graph: GraphParams {
+            placement: Some(GraphPlacement::new(GraphAnchor::TopLeft)),
+            label: "Theta spectrum (dB)".to_string(),
+            font_size: 14.0,
+            ..SpectrumParams::default().graph
+        },
+        sample_rate: 2.0 / time_fixed.timestep().as_secs_f32(),
+        downsample: 8,
+        ..Default::default()
+    });
+    commands.entity(spectrum).insert(Probe::<PendulumState>::named("theta"));
 
-    state.prealloc.y0[0] = state.theta;
-    state.prealloc.y0[1] = state.omega;
+    // Relative energy drift, shows the integrator quality
+    spawn_invariant_monitor(
+        &mut commands,
+        Box::new(model),
+        0,
+        GraphParams {
+            placement: Some(GraphPlacement::new(GraphAnchor::TopRight)),
+            max_points: 600,
+            line_color: Color::linear_rgba(0.6, 0.2, 3.0, 1.0),
+            label: "Energy drift (%)".to_string(),
+            x_title: "t (s)".to_string(),
+            x_gridlines: GridlineConfig::Fixed { spacing: 4.0 },
+            y_gridlines: GridlineConfig::Dynamic {
+                min_spacing: 0.01,
+                num_lines: 4,
+            },
+            // Fits the drift again once a transient has scrolled out
+            y_range: RangeMode::Autoscale { shrink_below: 0.5 },
+            min_y_range: 1e-4,
+            symmetric_y: true,
+            font_size: 14.0,
+            ..Default::default()
+        },
+    );
+}
 
-    rk4::rk4(t, dt, &mut state.prealloc);
-    state.theta = state.prealloc.out[0];
-    state.omega = state.prealloc.out[1];
+fn step_pendulum(time_fixed: Res<Time<Fixed>>, mut state: ResMut<PendulumState>) {
+    state.sim.advance(time_fixed.delta_secs() / 2.0);
 }
 
 fn draw_pendulum(
     mut painter: ShapePainter,
+    mut figure: ResMut<FigureCapture>,
     state: Res<PendulumState>,
-    mut q_mesh: Query<(&mut PhyzViz::utils::mesh_ribbon::MeshRibbon, &Name)>,
 ) {
     painter.scale(Vec3::splat(RENDER_SCALE));
 
-    let length: f32 = 2.0;
     let bob_radius = 0.12;
 
     let pivot = Vec3::ZERO;
-    let theta = state.theta;
-    let bob_pos = Vec3::new(length * theta.sin(), -length * theta.cos(), 0.0);
+    let bob_pos = state.bob_position();
 
     let base = painter.transform;
 
@@ -113,6 +182,7 @@ fn draw_pendulum(
     painter.thickness = 0.05;
     painter.set_color(Srgba { red: 4.0 * 165.0 / 255.0, green: 4.0 * 136.0 / 255.0, blue: 4.0 * 94.0 / 255.0, alpha: 1.0 });
     painter.line(pivot, bob_pos);
+    figure.line(&painter, pivot, bob_pos);
 
     // --- pivot circle at z = +0.001 ---
     let mut t = base;
@@ -123,6 +193,7 @@ fn draw_pendulum(
     painter.set_color(Srgba { red: 4.0 * 165.0 / 255.0, green: 4.0 * 136.0 / 255.0, blue: 4.0 * 94.0 / 255.0, alpha: 1.0 });
     painter.translate(pivot);
     painter.circle(0.07);
+    figure.circle(&painter, 0.07);
 
     // --- bob circle at z = +0.002 ---
     let mut t2 = base;
@@ -131,14 +202,16 @@ fn draw_pendulum(
     painter.translate(bob_pos);
     painter.set_color(Color::linear_rgba(3.0, 0.6, 0.2, 1.0));
     painter.circle(bob_radius);
+    figure.circle(&painter, bob_radius);
 
     painter.transform = base;
+}
 
-    // Move mesh ribbon position
-    for (mut ribbon, name) in q_mesh.iter_mut() {
-        if name.as_str() == "bob_mesh_ribbon" {
-            ribbon.current_position = bob_pos * RENDER_SCALE;
-        }
+// Move the bob entity followed by the ribbon, sampled at the end of the fixed step
+fn move_bob(state: Res<PendulumState>, mut q_bob: Query<(&mut Transform, &mut TrailSource), With<Bob>>) {
+    if let Ok((mut transform, mut source)) = q_bob.single_mut() {
+        transform.translation = state.bob_position();
+        source.scalar = state.omega().abs() * state.params.length;
     }
 }
 
@@ -165,11 +238,19 @@ fn main() {
         )
         .insert_resource(Time::<Fixed>::from_duration(Duration::from_secs_f64(1.0 / 120.0)))
         .add_plugins(Shape2dPlugin::default())
+        .add_plugins(MeshRibbonPlugin)
+        .add_plugins(GraphOverlayPlugin)
+        .add_plugins(GraphInteractionPlugin)
+        .add_plugins(GraphExportPlugin)
+        .add_plugins(FigureExportPlugin)
+        // Simulated time runs at half speed
+        .insert_resource(RibbonClock::new(0.5))
         .insert_resource(ClearColor(bevy::prelude::Color::Srgba(Srgba { red: 84.0 / 255.0, green: 18.0 / 255.0, blue: 18.0 / 255.0, alpha: 1.0 })))
         .add_systems(Startup, setup)
-        .add_systems(FixedUpdate, step_pendulum)
+        .add_systems(FixedUpdate, (step_pendulum, move_bob, probe_graphs::<PendulumState>, monitor_invariants::<PendulumState>).chain())
         .add_systems(Update, draw_pendulum)
-        .add_systems(Update, add_ribbon_position);
+        .add_systems(Update, draw_spectrum_widget)
+        .add_systems(Update, draw_graph_widget);
 
     #[cfg(feature = "fps_overlay")]
     app.add_plugins(FpsOverlayPlugin::default());
@@ -1 +1,2 @@
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod models;
\ No newline at end of file
@@ -1,5 +1,283 @@
-pub mod utils;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
 
-fn main() {
-    println!("Hello, world!");
+use PhyzViz::models::double_pendulum::DoublePendulum;
+use PhyzViz::models::lorenz::Lorenz;
+use PhyzViz::models::pendulum::SimplePendulum;
+use PhyzViz::utils::export::Trajectory;
+use PhyzViz::utils::integrator::{ExplicitEuler, Integrator};
+use PhyzViz::utils::rk4::RK4;
+use PhyzViz::utils::simulation::Simulation;
+use PhyzViz::utils::ODEs::ODEFunc;
+
+const USAGE: &str = "\
+Batch integration of the built-in models, trajectories are written as CSV or NumPy .npy
+
+Usage: PhyzViz --model <name> [options]
+
+Options:
+  --model <name>         pendulum, double-pendulum or lorenz
+  --param <name=value>   Set a model parameter (repeatable)
+  --y0 <v1,v2,...>       Initial state (defaults to the model's)
+  --integrator <name>    rk4 (default) or euler
+  --dt <seconds>         Integration step (default 0.001)
+  --duration <seconds>   Simulated duration (default 10)
+  --every <n>            Keep one sample out of every n steps (default 1)
+  --format <csv|npy>     Output format (default: from the output extension, else csv)
+  --output <path>        Output file (default: stdout, csv only)
+  --list                 List the models with their parameters and state
+  --help                 Show this message
+";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Npy,
+}
+
+struct Options {
+    model: String,
+    params: Vec<(String, f32)>,
+    y0: Option<Vec<f32>>,
+    integrator: String,
+    dt: f32,
+    duration: f32,
+    every: usize,
+    format: Option<Format>,
+    output: Option<String>,
+}
+
+/// Model, default initial state and state component names
+type BuiltModel = (Box<dyn ODEFunc + Send + Sync>, Vec<f32>, Vec<&'static str>);
+
+fn parse_f32(flag: &str, value: &str) -> Result<f32, String> {
+    value.trim().parse().map_err(|_| format!("invalid number '{}' for {}", value, flag))
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        model: String::new(),
+        params: Vec::new(),
+        y0: None,
+        integrator: "rk4".to_string(),
+        dt: 0.001,
+        duration: 10.0,
+        every: 1,
+        format: None,
+        output: None,
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--help" || flag == "-h" {
+            print!("{}", USAGE);
+            return Ok(None);
+        }
+        if flag == "--list" {
+            print_models();
+            return Ok(None);
+        }
+
+        let value = iter.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--model" => options.model = value.clone(),
+            "--param" => {
+                let (name, v) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected name=value for --param, got '{}'", value))?;
+                options.params.push((name.to_string(), parse_f32("--param", v)?));
+            }
+            "--y0" => {
+                let y0 = value.split(',').map(|v| parse_f32("--y0", v)).collect::<Result<Vec<_>, _>>()?;
+                options.y0 = Some(y0);
+            }
+            "--integrator" => options.integrator = value.clone(),
+            "--dt" => options.dt = parse_f32(flag, value)?,
+            "--duration" => options.duration = parse_f32(flag, value)?,
+            "--every" => {
+                options.every = value.parse().map_err(|_| format!("invalid count '{}' for --every", value))?;
+            }
+            "--format" => {
+                options.format = Some(match value.as_str() {
+                    "csv" => Format::Csv,
+                    "npy" => Format::Npy,
+                    _ => return Err(format!("unknown format '{}' (expected csv or npy)", value)),
+                });
+            }
+            "--output" => options.output = Some(value.clone()),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    if options.model.is_empty() {
+        return Err("missing --model".to_string());
+    }
+    if !options.dt.is_finite() || !options.duration.is_finite() {
+        return Err("--dt and --duration must be finite".to_string());
+    }
+    if options.dt <= 0.0 || options.duration < 0.0 || options.every == 0 {
+        return Err("--dt and --every must be positive, --duration non-negative".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn print_models() {
+    println!("pendulum         params: length, gravity          state: {}", SimplePendulum::STATE_NAMES.join(", "));
+    println!("double-pendulum  params: m1, m2, l1, l2, g        state: {}", DoublePendulum::STATE_NAMES.join(", "));
+    println!("lorenz           params: sigma, rho, beta         state: {}", Lorenz::STATE_NAMES.join(", "));
+}
+
+fn build_model(name: &str, params: &[(String, f32)]) -> Result<BuiltModel, String> {
+    match name {
+        "pendulum" => {
+            let mut model = SimplePendulum::default();
+            for (param, value) in params {
+                model.set_param(param, *value)?;
+            }
+            Ok((Box::new(model), vec![2.5, 0.0], SimplePendulum::STATE_NAMES.to_vec()))
+        }
+        "double-pendulum" => {
+            let mut model = DoublePendulum::default();
+            for (param, value) in params {
+                model.set_param(param, *value)?;
+            }
+            Ok((Box::new(model), vec![2.0, 0.0, 2.0, 0.0], DoublePendulum::STATE_NAMES.to_vec()))
+        }
+        "lorenz" => {
+            let mut model = Lorenz::default();
+            for (param, value) in params {
+                model.set_param(param, *value)?;
+            }
+            Ok((Box::new(model), vec![10.0, 10.0, 10.0], Lorenz::STATE_NAMES.to_vec()))
+        }
+        _ => Err(format!("unknown model '{}' (see --list)", name)),
+    }
+}
+
+fn build_integrator(name: &str, n: usize) -> Result<Box<dyn Integrator + Send + Sync>, String> {
+    match name {
+        "rk4" => Ok(Box::new(RK4::new(n))),
+        "euler" => Ok(Box::new(ExplicitEuler::new(n))),
+        _ => Err(format!("unknown integrator '{}' (expected rk4 or euler)", name)),
+    }
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let (ode, default_y0, state_names) = build_model(&options.model, &options.params)?;
+    let y0 = options.y0.unwrap_or(default_y0);
+    if y0.len() != state_names.len() {
+        return Err(format!(
+            "--y0 has {} values but {} expects {} ({})",
+            y0.len(),
+            options.model,
+            state_names.len(),
+            state_names.join(", ")
+        ));
+    }
+
+    let format = options.format.unwrap_or(match &options.output {
+        Some(path) if path.ends_with(".npy") => Format::Npy,
+        _ => Format::Csv,
+    });
+    if format == Format::Npy && options.output.is_none() {
+        return Err("npy output needs --output".to_string());
+    }
+
+    let integrator = build_integrator(&options.integrator, y0.len())?;
+    let mut sim = Simulation::new(ode, integrator, y0, options.dt);
+
+    let mut columns = vec!["t".to_string()];
+    columns.extend(state_names.iter().map(|s| s.to_string()));
+    let mut trajectory = Trajectory::new(columns);
+    trajectory.push_sample(sim.time(), sim.state());
+
+    let mut steps = 0usize;
+    sim.run_until(options.duration, |t, y| {
+        steps += 1;
+        if steps.is_multiple_of(options.every) {
+            trajectory.push_sample(t, y);
+        }
+    });
+
+    let written = match &options.output {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?;
+            let mut writer = BufWriter::new(file);
+            match format {
+                Format::Csv => trajectory.write_csv(&mut writer),
+                Format::Npy => trajectory.write_npy(&mut writer),
+            }
+            .and_then(|_| writer.flush())
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut writer = BufWriter::new(stdout.lock());
+            trajectory.write_csv(&mut writer).and_then(|_| writer.flush())
+        }
+    };
+    written.map_err(|e| format!("write failed: {}", e))?;
+
+    if let Some(path) = &options.output {
+        eprintln!("wrote {} samples of {} to {}", trajectory.num_rows(), options.model, path);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<Options>, String> {
+        let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse("--model lorenz --dt 0.01 --duration 2 --every 5 --param rho=28").unwrap().unwrap();
+        assert_eq!(options.model, "lorenz");
+        assert_eq!((options.dt, options.duration, options.every), (0.01, 2.0, 5));
+        assert_eq!(options.params, [("rho".to_string(), 28.0)]);
+    }
+
+    #[test]
+    fn rejects_bad_steps_and_durations() {
+        for args in [
+            "--model lorenz --dt 0",
+            "--model lorenz --dt -0.1",
+            "--model lorenz --dt NaN",
+            "--model lorenz --dt inf",
+            "--model lorenz --duration inf",
+            "--model lorenz --duration NaN",
+            "--model lorenz --duration -1",
+            "--model lorenz --every 0",
+            "--model lorenz --dt abc",
+        ] {
+            assert!(parse(args).is_err(), "accepted {}", args);
+        }
+    }
+
+    #[test]
+    fn rejects_missing_values() {
+        assert!(parse("--dt 0.1").is_err());
+        assert!(parse("--model").is_err());
+        assert!(parse("--model lorenz --format xml").is_err());
+    }
 }
@@ -0,0 +1,127 @@
+use crate::utils::invariants::Invariant;
+use crate::utils::ODEs::ODEFunc;
+
+/// Double pendulum, state vector is [theta1, omega1, theta2, omega2]
+#[derive(Clone)]
+pub struct DoublePendulum {
+    pub m1: f32,
+    pub m2: f32,
+    pub l1: f32,
+    pub l2: f32,
+    pub g: f32,
+}
+
+impl Default for DoublePendulum {
+    fn default() -> Self {
+        Self { m1: 1.0, m2: 1.0, l1: 1.0, l2: 1.0, g: 9.81 }
+    }
+}
+
+// Source : https://web.mit.edu/jorloff/www/chaosTalk/double-pendulum/double-pendulum-en.html
+impl ODEFunc for DoublePendulum {
+    fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
+        // State variables
+        let theta1 = y[0];
+        let omega1 = y[1];
+        let theta2 = y[2];
+        let omega2 = y[3];
+
+        let m1 = self.m1;
+        let m2 = self.m2;
+        let l1 = self.l1;
+        let l2 = self.l2;
+        let g = self.g;
+
+        // Common terms
+        let delta = theta1 - theta2;
+        let denom = 2.0 * m1 + m2 - m2 * (2.0 * theta1 - 2.0 * theta2).cos();
+
+        // Equations of motion
+        let dtheta1_dt = omega1;
+        let dtheta2_dt = omega2;
+
+        out[0] = dtheta1_dt;
+        out[2] = dtheta2_dt;
+
+        let domega1_dt = (
+            -g * (2.0 * m1 + m2) * theta1.sin()
+            - m2 * g * (theta1 - 2.0 * theta2).sin()
+            - 2.0 * m2 * delta.sin()
+                * (omega2.powi(2) * l2 + omega1.powi(2) * l1 * delta.cos())
+        ) / (l1 * denom);
+
+        out[1] = domega1_dt;
+
+        let domega2_dt = (
+            2.0 * delta.sin()
+                * (omega1.powi(2) * l1 * (m1 + m2)
+                + g * (m1 + m2) * theta1.cos()
+                + omega2.powi(2) * l2 * m2 * delta.cos())
+        ) / (l2 * denom);
+
+        out[3] = domega2_dt;
+    }
+}
+
+impl DoublePendulum {
+    pub const STATE_NAMES: [&'static str; 4] = ["theta1", "omega1", "theta2", "omega2"];
+
+    pub fn set_param(&mut self, name: &str, value: f32) -> Result<(), String> {
+        match name {
+            "m1" => self.m1 = value,
+            "m2" => self.m2 = value,
+            "l1" => self.l1 = value,
+            "l2" => self.l2 = value,
+            "g" => self.g = value,
+            _ => return Err(format!("unknown double pendulum parameter '{}' (expected m1, m2, l1, l2, g)", name)),
+        }
+        Ok(())
+    }
+
+    /// Calculate kinetic energy of the system
+    pub fn kinetic_energy(&self, theta1: f32, omega1: f32, theta2: f32, omega2: f32) -> (f32, f32) {
+        let m1 = self.m1;
+        let m2 = self.m2;
+        let l1 = self.l1;
+        let l2 = self.l2;
+
+        let delta = theta1 - theta2;
+
+        // Kinetic energy formula for double pendulum
+        let ke1 = 0.5 * m1 * (l1 * omega1).powi(2);
+        let ke2 = 0.5 * m2 * (
+            (l1 * omega1).powi(2) + (l2 * omega2).powi(2)
+            + 2.0 * l1 * l2 * omega1 * omega2 * delta.cos()
+        );
+
+        (ke1, ke2)
+    }
+
+    /// Calculate potential energy of the system
+    pub fn potential_energy(&self, theta1: f32, theta2: f32) -> (f32, f32) {
+        let m1 = self.m1;
+        let m2 = self.m2;
+        let l1 = self.l1;
+        let l2 = self.l2;
+        let g = self.g;
+
+        // Taking the pivot as zero potential energy reference
+        let h1 = -l1 * theta1.cos();
+        let h2 = -l1 * theta1.cos() - l2 * theta2.cos();
+
+        (m1 * g * h1, m2 * g * h2)
+    }
+}
+
+impl Invariant for DoublePendulum {
+    fn invariant_names(&self) -> Vec<&'static str> {
+        vec!["Energy"]
+    }
+
+    fn invariants(&self, y: &[f32], out: &mut Vec<f32>) {
+        let (ke1, ke2) = self.kinetic_energy(y[0], y[1], y[2], y[3]);
+        let (pe1, pe2) = self.potential_energy(y[0], y[2]);
+        out.clear();
+        out.push(ke1 + ke2 + pe1 + pe2);
+    }
+}
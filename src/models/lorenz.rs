@@ -0,0 +1,49 @@
+use crate::utils::ODEs::ODEFunc;
+
+/// Lorenz system, state vector is [x, y, z]
+#[derive(Clone)]
+pub struct Lorenz {
+    pub sigma: f32,
+    pub rho: f32,
+    pub beta: f32,
+}
+
+impl Lorenz {
+    pub const STATE_NAMES: [&'static str; 3] = ["x", "y", "z"];
+
+    pub fn set_param(&mut self, name: &str, value: f32) -> Result<(), String> {
+        match name {
+            "sigma" => self.sigma = value,
+            "rho" => self.rho = value,
+            "beta" => self.beta = value,
+            _ => return Err(format!("unknown lorenz parameter '{}' (expected sigma, rho, beta)", name)),
+        }
+        Ok(())
+    }
+}
+
+impl Default for Lorenz {
+    fn default() -> Self {
+        Self {
+            sigma: 10.0,
+            rho: 28.0,
+            beta: 8.0 / 3.0,
+        }
+    }
+}
+
+impl ODEFunc for Lorenz {
+    fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
+        let x = y[0];
+        let z = y[2];
+        let dy = y[1];
+
+        let dxdt = self.sigma * (dy - x);
+        let dydt = x * (self.rho - z) - dy;
+        let dzdt = x * dy - self.beta * z;
+
+        out[0] = dxdt;
+        out[1] = dydt;
+        out[2] = dzdt;
+    }
+}
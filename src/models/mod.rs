@@ -0,0 +1,3 @@
+pub mod pendulum;
+pub mod double_pendulum;
+pub mod lorenz;
@@ -0,0 +1,53 @@
+use crate::utils::invariants::Invariant;
+use crate::utils::ODEs::ODEFunc;
+
+/// Simple pendulum, state vector is [theta, omega]
+#[derive(Clone)]
+pub struct SimplePendulum {
+    pub length: f32,
+    pub gravity: f32,
+}
+
+impl SimplePendulum {
+    pub const STATE_NAMES: [&'static str; 2] = ["theta", "omega"];
+
+    pub fn set_param(&mut self, name: &str, value: f32) -> Result<(), String> {
+        match name {
+            "length" => self.length = value,
+            "gravity" => self.gravity = value,
+            _ => return Err(format!("unknown pendulum parameter '{}' (expected length, gravity)", name)),
+        }
+        Ok(())
+    }
+}
+
+impl Default for SimplePendulum {
+    fn default() -> Self {
+        Self { length: 2.0, gravity: 9.81 }
+    }
+}
+
+impl ODEFunc for SimplePendulum {
+    fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
+        let theta = y[0];
+        let omega = y[1];
+        let dtheta_dt = omega;
+        let domega_dt = -(self.gravity / self.length) * theta.sin();
+        out[0] = dtheta_dt;
+        out[1] = domega_dt;
+    }
+}
+
+impl Invariant for SimplePendulum {
+    fn invariant_names(&self) -> Vec<&'static str> {
+        vec!["Energy"]
+    }
+
+    // Energy per unit mass, pivot as zero potential energy reference
+    fn invariants(&self, y: &[f32], out: &mut Vec<f32>) {
+        let theta = y[0];
+        let omega = y[1];
+        out.clear();
+        out.push(0.5 * (self.length * omega).powi(2) - self.gravity * self.length * theta.cos());
+    }
+}
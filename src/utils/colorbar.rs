@@ -0,0 +1,175 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToPacked;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::sprite::Anchor;
+
+use crate::utils::colormap::Colormap;
+use crate::utils::mesh_ribbon::MeshRibbon;
+
+/// Number of texels along the colorbar gradient
+pub(crate) const COLORBAR_RESOLUTION: u32 = 128;
+
+#[derive(Clone)]
+pub struct ColorbarParams {
+    /// Position on screen (top-left corner)
+    pub position: Vec2,
+    /// Size of the bar on screen, the low end of the range is at the bottom
+    pub size: Vec2,
+    /// Number of value labels along the bar, ends included
+    pub num_ticks: usize,
+    /// Label above the bar
+    pub label: String,
+    /// Text color
+    pub text_color: Color,
+    /// Font size for labels
+    pub font_size: f32,
+}
+
+impl Default for ColorbarParams {
+    fn default() -> Self {
+        Self {
+            position: Vec2::new(560.0, 100.0),
+            size: Vec2::new(16.0, 200.0),
+            num_ticks: 5,
+            label: "".to_string(),
+            text_color: Color::srgba(0.9, 0.9, 0.9, 1.0),
+            font_size: 12.0,
+        }
+    }
+}
+
+/// Color bar legend of a ribbon colored by `GradientAxis::Scalar`,
+/// follows the ribbon's colormap and scalar range
+#[derive(Component)]
+pub struct Colorbar {
+    pub params: ColorbarParams,
+    /// Ribbon entity whose colormap and range are shown
+    pub ribbon: Entity,
+    image: Handle<Image>,
+    pub(crate) tick_texts: Vec<Entity>,
+    /// Colormap and range currently shown, to only redraw on change
+    pub(crate) colormap: Option<Colormap>,
+    pub(crate) range: (f32, f32),
+}
+
+impl Colorbar {
+    fn write_texture(&self, colormap: &Colormap, image: &mut Image) {
+        let Some(data) = image.data.as_mut() else {
+            return;
+        };
+        let last = (COLORBAR_RESOLUTION - 1) as f32;
+        for (row, texel) in data.chunks_exact_mut(4).enumerate() {
+            // Row 0 is the top of the bar, where the range ends
+            let t = 1.0 - row as f32 / last;
+            texel.copy_from_slice(&colormap.sample(t).to_srgba().to_u8_array());
+        }
+    }
+}
+
+/// System to keep color bars in sync with their ribbon
+pub fn update_colorbar(
+    mut query: Query<&mut Colorbar>,
+    q_ribbon: Query<&MeshRibbon>,
+    mut images: ResMut<Assets<Image>>,
+    mut q_text: Query<&mut Text2d>,
+) {
+    for mut colorbar in query.iter_mut() {
+        let Ok(ribbon) = q_ribbon.get(colorbar.ribbon) else {
+            continue;
+        };
+
+        if colorbar.colormap.as_ref() != Some(&ribbon.params.colormap) {
+            if let Some(image) = images.get_mut(&colorbar.image) {
+                colorbar.write_texture(&ribbon.params.colormap, image);
+            }
+            colorbar.colormap = Some(ribbon.params.colormap.clone());
+        }
+
+        let range = ribbon.scalar_range();
+        if range == colorbar.range {
+            continue;
+        }
+        colorbar.range = range;
+
+        let last = colorbar.tick_texts.len().saturating_sub(1).max(1) as f32;
+        for (i, &entity) in colorbar.tick_texts.iter().enumerate() {
+            if let Ok(mut text) = q_text.get_mut(entity) {
+                let value = range.0 + (range.1 - range.0) * i as f32 / last;
+                text.0 = format!("{:.2}", value);
+            }
+        }
+    }
+}
+
+/// Spawn a color bar for `ribbon` (rendered as a sprite with its labels)
+pub fn spawn_colorbar(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    ribbon: Entity,
+    params: ColorbarParams,
+) -> Entity {
+    let image = Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: COLORBAR_RESOLUTION,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    let image_handle = images.add(image);
+
+    let pos = params.position;
+    let size = params.size;
+
+    commands.spawn((
+        Text2d::new(&params.label),
+        TextFont {
+            font_size: params.font_size,
+            ..default()
+        },
+        TextColor(params.text_color),
+        Transform::from_translation(Vec3::new(pos.x, pos.y + 5.0, 0.2)),
+        Anchor::BOTTOM_LEFT,
+    ));
+
+    // Ticks from the bottom (low end) to the top, filled in by `update_colorbar`
+    let num_ticks = params.num_ticks.max(2);
+    let tick_texts = (0..num_ticks)
+        .map(|i| {
+            let y = pos.y - size.y + size.y * i as f32 / (num_ticks - 1) as f32;
+            commands.spawn((
+                Text2d::new(""),
+                TextFont {
+                    font_size: params.font_size,
+                    ..default()
+                },
+                TextColor(params.text_color),
+                Transform::from_translation(Vec3::new(pos.x + size.x + 4.0, y, 0.2)),
+                Anchor::CENTER_LEFT,
+            )).id()
+        })
+        .collect();
+
+    commands.spawn((
+        Sprite {
+            image: image_handle.clone(),
+            custom_size: Some(size),
+            ..default()
+        },
+        Anchor::TOP_LEFT,
+        Transform::from_translation(Vec3::new(pos.x, pos.y, 0.0)),
+        Colorbar {
+            params,
+            ribbon,
+            image: image_handle,
+            tick_texts,
+            colormap: None,
+            range: (f32::NAN, f32::NAN),
+        },
+        Name::new("Colorbar"),
+    )).id()
+}
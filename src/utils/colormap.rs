@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+/// Evenly spaced color stops, interpolated in linear RGB
+#[derive(Clone, Debug, PartialEq)]
+pub struct Colormap {
+    pub colors: Vec<Color>,
+}
+
+impl Colormap {
+    pub fn new(colors: Vec<Color>) -> Self {
+        assert!(!colors.is_empty(), "a colormap needs at least one color");
+        Self { colors }
+    }
+
+    /// Single color, `sample` returns it everywhere
+    pub fn solid(color: Color) -> Self {
+        Self { colors: vec![color] }
+    }
+
+    /// Perceptually uniform, dark purple to yellow (matplotlib's default)
+    pub fn viridis() -> Self {
+        Self::from_hex(&[
+            "440154", "482878", "3e4a89", "31688e", "26828e", "1f9e89", "35b779", "6dcd59", "b4de2c", "fde725",
+        ])
+    }
+
+    /// Perceptually uniform, black to light yellow through magenta
+    pub fn magma() -> Self {
+        Self::from_hex(&[
+            "000004", "180f3e", "451077", "721f81", "9f2f7f", "cd4071", "f1605d", "fd9567", "fec98d", "fcfdbf",
+        ])
+    }
+
+    /// Diverging blue to red through light grey, for signed quantities
+    pub fn coolwarm() -> Self {
+        Self::from_hex(&[
+            "3b4cc0", "6282ea", "8db0fe", "b8d0f9", "dddddd", "f5c4ad", "f49a7b", "de604d", "b40426",
+        ])
+    }
+
+    fn from_hex(stops: &[&str]) -> Self {
+        Self::new(
+            stops
+                .iter()
+                .map(|hex| Color::Srgba(Srgba::hex(hex).expect("invalid colormap stop")))
+                .collect(),
+        )
+    }
+
+    /// Color at `t` in [0, 1] (clamped), the first stop is at 0 and the last at 1
+    pub fn sample(&self, t: f32) -> Color {
+        let last = self.colors.len() - 1;
+        if last == 0 {
+            return self.colors[0];
+        }
+        let x = t.clamp(0.0, 1.0) * last as f32;
+        let i = (x.floor() as usize).min(last - 1);
+        let a = self.colors[i].to_linear();
+        let b = self.colors[i + 1].to_linear();
+        Color::LinearRgba(a.mix(&b, x - i as f32))
+    }
+
+    /// Linear RGBA stops for a shader uniform array of `N` entries and the number of stops used.
+    /// Colormaps with more than `N` stops are resampled evenly.
+    pub fn to_uniform<const N: usize>(&self) -> ([Vec4; N], u32) {
+        let mut stops = [Vec4::ZERO; N];
+        let count = self.colors.len().min(N);
+        for (i, stop) in stops.iter_mut().take(count).enumerate() {
+            *stop = if self.colors.len() <= N {
+                self.colors[i].to_linear().to_vec4()
+            } else {
+                let t = if count > 1 { i as f32 / (count - 1) as f32 } else { 0.0 };
+                self.sample(t).to_linear().to_vec4()
+            };
+        }
+        (stops, count as u32)
+    }
+}
+
+impl Default for Colormap {
+    fn default() -> Self {
+        Self::solid(Color::WHITE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_color(actual: Color, expected: Color) {
+        let (a, b) = (actual.to_linear().to_vec4(), expected.to_linear().to_vec4());
+        assert!((a - b).abs().max_element() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn sample_hits_the_stops() {
+        for colormap in [Colormap::viridis(), Colormap::magma(), Colormap::coolwarm()] {
+            let last = colormap.colors.len() - 1;
+            assert_color(colormap.sample(0.0), colormap.colors[0]);
+            assert_color(colormap.sample(1.0), colormap.colors[last]);
+            for (i, &stop) in colormap.colors.iter().enumerate() {
+                assert_color(colormap.sample(i as f32 / last as f32), stop);
+            }
+            // Out of range values are clamped to the end stops
+            assert_color(colormap.sample(-0.5), colormap.colors[0]);
+            assert_color(colormap.sample(1.5), colormap.colors[last]);
+        }
+    }
+
+    #[test]
+    fn sample_mixes_in_linear_rgb() {
+        let colormap = Colormap::new(vec![Color::BLACK, Color::WHITE, Color::srgb(1.0, 0.0, 0.0)]);
+        assert_color(colormap.sample(0.25), Color::linear_rgb(0.5, 0.5, 0.5));
+        assert_color(colormap.sample(0.75), Color::linear_rgb(1.0, 0.5, 0.5));
+        assert_color(Colormap::solid(Color::WHITE).sample(0.3), Color::WHITE);
+    }
+
+    #[test]
+    fn uniform_resamples_long_colormaps() {
+        let colormap = Colormap::viridis();
+        let (stops, count) = colormap.to_uniform::<16>();
+        assert_eq!(count, 10);
+        assert_eq!(stops[9], colormap.colors[9].to_linear().to_vec4());
+        assert_eq!(stops[10], Vec4::ZERO);
+
+        let (stops, count) = colormap.to_uniform::<4>();
+        assert_eq!(count, 4);
+        assert!((stops[0] - colormap.colors[0].to_linear().to_vec4()).abs().max_element() < 1e-5);
+        assert!((stops[3] - colormap.colors[9].to_linear().to_vec4()).abs().max_element() < 1e-5);
+        assert!((stops[1] - colormap.sample(1.0 / 3.0).to_linear().to_vec4()).abs().max_element() < 1e-5);
+    }
+}
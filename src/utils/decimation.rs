@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+/// Indices of the points kept by the Ramer–Douglas–Peucker simplification of the polyline
+/// `points`, in order. Every dropped point lies within `tolerance` of the simplified polyline
+/// and both endpoints are kept.
+pub fn ramer_douglas_peucker(points: &[Vec3], tolerance: f32) -> Vec<usize> {
+    if points.len() <= 2 {
+        return (0..points.len()).collect();
+    }
+    let last = points.len() - 1;
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[last] = true;
+
+    // Explicit stack, histories of 100k points would overflow a recursive version
+    let mut stack = vec![(0, last)];
+    while let Some((start, end)) = stack.pop() {
+        let mut farthest = start;
+        let mut max_distance = 0.0;
+        for i in start + 1..end {
+            let distance = distance_to_segment(points[i], points[start], points[end]);
+            if distance > max_distance {
+                farthest = i;
+                max_distance = distance;
+            }
+        }
+        if max_distance > tolerance {
+            keep[farthest] = true;
+            stack.push((start, farthest));
+            stack.push((farthest, end));
+        }
+    }
+
+    keep.iter()
+        .enumerate()
+        .filter_map(|(i, &kept)| kept.then_some(i))
+        .collect()
+}
+
+/// Distance from `point` to the segment from `a` to `b`
+fn distance_to_segment(point: Vec3, a: Vec3, b: Vec3) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared <= f32::EPSILON {
+        return point.distance(a);
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// Indices of the points kept by min/max decimation of the polyline `points` into columns of
+/// `column_width` starting at `left`, in order. Each run of consecutive points in one column
+/// keeps its first, lowest, highest and last point, so a line drawn through the kept points
+/// covers the same pixels with at most four points per column.
+pub fn min_max_columns(points: &[Vec2], left: f32, column_width: f32) -> Vec<usize> {
+    let column = |point: Vec2| ((point.x - left) / column_width).floor();
+    let mut kept = Vec::new();
+    let mut start = 0;
+    while start < points.len() {
+        let current = column(points[start]);
+        let mut end = start + 1;
+        let (mut lowest, mut highest) = (start, start);
+        while end < points.len() && column(points[end]) == current {
+            if points[end].y < points[lowest].y {
+                lowest = end;
+            }
+            if points[end].y > points[highest].y {
+                highest = end;
+            }
+            end += 1;
+        }
+        let mut run = [start, lowest, highest, end - 1];
+        run.sort_unstable();
+        for index in run {
+            if kept.last() != Some(&index) {
+                kept.push(index);
+            }
+        }
+        start = end;
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Noisy spiral, with sharp corners every so often
+    fn spiral(count: usize) -> Vec<Vec3> {
+        (0..count)
+            .map(|i| {
+                let a = i as f32 * 0.01;
+                let jitter = if i % 37 == 0 { 3.0 } else { 0.0 };
+                Vec3::new(a.cos(), a.sin(), 0.0) * (50.0 + a * 5.0 + jitter)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rdp_keeps_endpoints_within_tolerance() {
+        let points = spiral(5000);
+        let tolerance = 0.5;
+        let kept = ramer_douglas_peucker(&points, tolerance);
+        assert_eq!(kept.first(), Some(&0));
+        assert_eq!(kept.last(), Some(&(points.len() - 1)));
+        assert!(kept.len() < points.len());
+        for pair in kept.windows(2) {
+            assert!(pair[0] < pair[1]);
+            for i in pair[0] + 1..pair[1] {
+                let distance = distance_to_segment(points[i], points[pair[0]], points[pair[1]]);
+                assert!(distance <= tolerance, "point {} is {} off", i, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn rdp_collapses_straight_lines() {
+        let points: Vec<Vec3> = (0..100).map(|i| Vec3::new(i as f32, 2.0 * i as f32, 0.0)).collect();
+        assert_eq!(ramer_douglas_peucker(&points, 1e-3), [0, 99]);
+        assert_eq!(ramer_douglas_peucker(&points[..2], 1e-3), [0, 1]);
+        assert!(ramer_douglas_peucker(&[], 1.0).is_empty());
+    }
+
+    #[test]
+    fn rdp_handles_long_histories() {
+        // As long as the histories ribbons keep
+        let points = spiral(100_000);
+        let kept = ramer_douglas_peucker(&points, 1.0);
+        assert_eq!(kept.last(), Some(&(points.len() - 1)));
+    }
+
+    /// Noisy sine over x in [0, 100)
+    fn signal(count: usize) -> Vec<Vec2> {
+        (0..count)
+            .map(|i| {
+                let x = 100.0 * i as f32 / count as f32;
+                let noise = ((i * 7919) % 101) as f32 / 101.0 - 0.5;
+                Vec2::new(x, x.sin() + noise)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn min_max_columns_keep_each_column_extrema() {
+        let points = signal(100_000);
+        let (left, width) = (0.0, 100.0 / 300.0);
+        let kept = min_max_columns(&points, left, width);
+
+        assert_eq!(kept.first(), Some(&0));
+        assert_eq!(kept.last(), Some(&(points.len() - 1)));
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+        // At most four points per column
+        assert!(kept.len() <= 4 * 301, "{} points kept", kept.len());
+
+        let column = |point: Vec2| ((point.x - left) / width).floor() as i64;
+        let mut start = 0;
+        while start < points.len() {
+            let end = (start..points.len()).find(|&i| column(points[i]) != column(points[start])).unwrap_or(points.len());
+            let lowest = (start..end).map(|i| points[i].y).fold(f32::INFINITY, f32::min);
+            let highest = (start..end).map(|i| points[i].y).fold(f32::NEG_INFINITY, f32::max);
+            let kept_here: Vec<f32> = kept.iter().filter(|&&i| (start..end).contains(&i)).map(|&i| points[i].y).collect();
+            assert!(kept_here.contains(&lowest) && kept_here.contains(&highest), "column at {}", points[start].x);
+            assert!(kept.contains(&start) && kept.contains(&(end - 1)));
+            start = end;
+        }
+    }
+
+    #[test]
+    fn min_max_columns_keep_sparse_points() {
+        let points: Vec<Vec2> = (0..10).map(|i| Vec2::new(i as f32 * 10.0, i as f32)).collect();
+        assert_eq!(min_max_columns(&points, 0.0, 1.0), (0..10).collect::<Vec<_>>());
+        assert!(min_max_columns(&[], 0.0, 1.0).is_empty());
+    }
+
+}
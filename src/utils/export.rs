@@ -0,0 +1,122 @@
+use std::io::{self, Write};
+
+/// Row-major table of samples with named columns, written out as CSV or NumPy `.npy`
+#[derive(Clone, Default)]
+pub struct Trajectory {
+    pub columns: Vec<String>,
+    /// Flattened rows, `data.len()` is a multiple of `columns.len()`
+    pub data: Vec<f32>,
+}
+
+impl Trajectory {
+    pub fn new(columns: Vec<String>) -> Self {
+        Self {
+            columns,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        if self.columns.is_empty() {
+            0
+        } else {
+            self.data.len() / self.columns.len()
+        }
+    }
+
+    /// Append a row, `row.len()` must match the number of columns
+    pub fn push_row(&mut self, row: &[f32]) {
+        assert_eq!(row.len(), self.columns.len(), "row length does not match the number of columns");
+        self.data.extend_from_slice(row);
+    }
+
+    /// Append a `(t, state...)` sample
+    pub fn push_sample(&mut self, t: f32, state: &[f32]) {
+        assert_eq!(state.len() + 1, self.columns.len(), "state length does not match the number of columns");
+        self.data.push(t);
+        self.data.extend_from_slice(state);
+    }
+
+    /// Write a header line followed by one line per row
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", self.columns.join(","))?;
+        for row in self.data.chunks(self.columns.len().max(1)) {
+            let line: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+            writeln!(writer, "{}", line.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Write a NumPy v1.0 `.npy` file holding a `(rows, columns)` little-endian float32 array.
+    /// Column names are not part of the format.
+    pub fn write_npy<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.num_rows(),
+            self.columns.len()
+        );
+        // Magic (6) + version (2) + header length (2) + header, padded to a multiple of 64 and ending with '\n'
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+
+        writer.write_all(b"\x93NUMPY")?;
+        writer.write_all(&[1, 0])?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        for value in &self.data {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trajectory(columns: usize, rows: usize) -> Trajectory {
+        let mut trajectory = Trajectory::new((0..columns).map(|i| format!("c{}", i)).collect());
+        for row in 0..rows {
+            let values: Vec<f32> = (0..columns).map(|column| (row * columns + column) as f32).collect();
+            trajectory.push_row(&values);
+        }
+        trajectory
+    }
+
+    #[test]
+    fn npy_header_is_aligned() {
+        // Shapes of different widths move the header length around
+        for (columns, rows) in [(1, 0), (3, 7), (4, 1000), (12, 123_456)] {
+            let trajectory = trajectory(columns, rows);
+            let mut bytes = Vec::new();
+            trajectory.write_npy(&mut bytes).unwrap();
+
+            assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+            let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+            let data_start = 10 + header_len;
+            assert_eq!(data_start % 64, 0, "data of a {}x{} array starts at {}", rows, columns, data_start);
+            let header = std::str::from_utf8(&bytes[10..data_start]).unwrap();
+            assert!(header.ends_with('\n'));
+            assert!(header.contains(&format!("'shape': ({}, {}), ", rows, columns)), "{}", header);
+            assert_eq!(bytes.len() - data_start, 4 * rows * columns);
+        }
+    }
+
+    #[test]
+    fn npy_data_is_little_endian_rows() {
+        let trajectory = trajectory(2, 3);
+        let mut bytes = Vec::new();
+        trajectory.write_npy(&mut bytes).unwrap();
+        let data_start = 10 + u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let data: Vec<f32> = bytes[data_start..].chunks(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        assert_eq!(data, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn csv_has_a_header_and_one_line_per_row() {
+        let mut bytes = Vec::new();
+        trajectory(2, 2).write_csv(&mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), "c0,c1\n0,1\n2,3\n");
+    }
+}
@@ -0,0 +1,933 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_vector_shapes::prelude::*;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::utils::colorbar::{Colorbar, COLORBAR_RESOLUTION};
+use crate::utils::graph::{GraphWidget, SeriesMark, GRID_THICKNESS, LINE_THICKNESS};
+use crate::utils::graph_overlay::GraphOverlayCamera;
+use crate::utils::histogram::HistogramWidget;
+use crate::utils::mesh_ribbon::MeshRibbon;
+use crate::utils::spectrum::SpectrumWidget;
+
+/// Control points of a quarter circle as a cubic Bézier curve, relative to the radius
+const BEZIER_CIRCLE: f32 = 0.552_284_8;
+
+/// Height of capital letters relative to the font size, to place text by its top or middle
+const CAP_HEIGHT: f32 = 0.72;
+
+/// Average advance of a Helvetica character relative to the font size, to align PDF text
+const CHAR_WIDTH: f32 = 0.5;
+
+/// Horizontal alignment of text on its position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Start,
+    Middle,
+    End,
+}
+
+/// Vertical alignment of text on its position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextBaseline {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Element of a figure, in figure pixels with y pointing down.
+/// Colors are linear and may exceed 1 (glow), they are scaled back keeping their hue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FigureShape {
+    Line { from: Vec2, to: Vec2, width: f32, color: Color },
+    Polyline { points: Vec<Vec2>, width: f32, color: Color },
+    /// Filled polygon
+    Polygon { points: Vec<Vec2>, color: Color },
+    /// Filled circle, or its outline of width `stroke`
+    Circle { center: Vec2, radius: f32, color: Color, stroke: Option<f32> },
+    /// Text aligned on `position`, turned counter-clockwise around it by `rotation` radians
+    Text {
+        position: Vec2,
+        text: String,
+        size: f32,
+        color: Color,
+        align: TextAlign,
+        baseline: TextBaseline,
+        rotation: f32,
+    },
+}
+
+impl FigureShape {
+    /// Text placed like a `Text2d` with `anchor`
+    pub fn text(position: Vec2, text: impl Into<String>, size: f32, color: Color, anchor: Anchor) -> Self {
+        let anchor = anchor.as_vec();
+        let align = if anchor.x < -0.25 {
+            TextAlign::Start
+        } else if anchor.x > 0.25 {
+            TextAlign::End
+        } else {
+            TextAlign::Middle
+        };
+        let baseline = if anchor.y > 0.25 {
+            TextBaseline::Top
+        } else if anchor.y < -0.25 {
+            TextBaseline::Bottom
+        } else {
+            TextBaseline::Middle
+        };
+        Self::Text { position, text: text.into(), size, color, align, baseline, rotation: 0.0 }
+    }
+
+    /// The same shape with text turned counter-clockwise by `angle` radians, other shapes unchanged
+    pub fn rotated(mut self, angle: f32) -> Self {
+        if let Self::Text { rotation, .. } = &mut self {
+            *rotation += angle;
+        }
+        self
+    }
+}
+
+/// Vector figure of the scene, written as SVG or PDF
+#[derive(Debug, Clone, Default)]
+pub struct Figure {
+    /// Size in pixels, the viewport of the exported camera
+    pub size: Vec2,
+    pub background: Option<Color>,
+    /// Shapes in drawing order
+    pub shapes: Vec<FigureShape>,
+}
+
+impl Figure {
+    pub fn new(size: Vec2) -> Self {
+        Self { size, ..default() }
+    }
+
+    pub fn push(&mut self, shape: FigureShape) {
+        self.shapes.push(shape);
+    }
+
+    /// Write the figure as an SVG document
+    pub fn write_svg<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (width, height) = (self.size.x, self.size.y);
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.2} {height:.2}">"#
+        )?;
+        if let Some(background) = self.background {
+            let (rgb, alpha) = printable(background);
+            writeln!(writer, r#"<rect width="100%" height="100%" fill="{}" fill-opacity="{alpha:.3}"/>"#, svg_color(rgb))?;
+        }
+        for shape in &self.shapes {
+            match shape {
+                FigureShape::Line { from, to, width, color } => {
+                    let (rgb, alpha) = printable(*color);
+                    writeln!(
+                        writer,
+                        r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-opacity="{alpha:.3}" stroke-width="{width:.3}" stroke-linecap="round"/>"#,
+                        from.x, from.y, to.x, to.y, svg_color(rgb)
+                    )?;
+                }
+                FigureShape::Polyline { points, width, color } => {
+                    let (rgb, alpha) = printable(*color);
+                    writeln!(
+                        writer,
+                        r#"<polyline points="{}" fill="none" stroke="{}" stroke-opacity="{alpha:.3}" stroke-width="{width:.3}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                        svg_points(points), svg_color(rgb)
+                    )?;
+                }
+                FigureShape::Polygon { points, color } => {
+                    let (rgb, alpha) = printable(*color);
+                    writeln!(
+                        writer,
+                        r#"<polygon points="{}" fill="{}" fill-opacity="{alpha:.3}"/>"#,
+                        svg_points(points), svg_color(rgb)
+                    )?;
+                }
+                FigureShape::Circle { center, radius, color, stroke } => {
+                    let (rgb, alpha) = printable(*color);
+                    let paint = match stroke {
+                        Some(width) => format!(
+                            r#"fill="none" stroke="{}" stroke-opacity="{alpha:.3}" stroke-width="{width:.3}""#,
+                            svg_color(rgb)
+                        ),
+                        None => format!(r#"fill="{}" fill-opacity="{alpha:.3}""#, svg_color(rgb)),
+                    };
+                    writeln!(writer, r#"<circle cx="{:.2}" cy="{:.2}" r="{radius:.3}" {paint}/>"#, center.x, center.y)?;
+                }
+                FigureShape::Text { position, text, size, color, align, baseline, rotation } => {
+                    let (rgb, alpha) = printable(*color);
+                    let anchor = match align {
+                        TextAlign::Start => "start",
+                        TextAlign::Middle => "middle",
+                        TextAlign::End => "end",
+                    };
+                    // SVG angles run clockwise with y down
+                    let transform = if *rotation != 0.0 {
+                        format!(r#" transform="rotate({:.2} {:.2} {:.2})""#, -rotation.to_degrees(), position.x, position.y)
+                    } else {
+                        String::new()
+                    };
+                    writeln!(
+                        writer,
+                        r#"<text x="{:.2}" y="{:.2}"{transform} font-family="Helvetica, Arial, sans-serif" font-size="{size:.2}" text-anchor="{anchor}" fill="{}" fill-opacity="{alpha:.3}">{}</text>"#,
+                        position.x,
+                        baseline_y(position.y, *size, *baseline),
+                        svg_color(rgb),
+                        escape_xml(text)
+                    )?;
+                }
+            }
+        }
+        writeln!(writer, "</svg>")
+    }
+
+    /// Write the figure as a single page PDF, one point per figure pixel.
+    /// Text is set in the built-in Helvetica, so only ASCII characters are kept.
+    pub fn write_pdf<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let height = self.size.y;
+        // PDF space has y pointing up
+        let flip = |p: Vec2| Vec2::new(p.x, height - p.y);
+        let mut content = String::new();
+        // Opacity levels used, each gets a graphics state `/A<level>`
+        let mut alphas = [false; 256];
+        let mut set_paint = |content: &mut String, color: Color, stroke: bool| {
+            let (rgb, alpha) = printable(color);
+            let level = (alpha * 255.0).round() as usize;
+            alphas[level] = true;
+            let operator = if stroke { "RG" } else { "rg" };
+            let _ = writeln!(content, "/A{level} gs {:.4} {:.4} {:.4} {operator}", rgb[0], rgb[1], rgb[2]);
+        };
+        let path = |content: &mut String, points: &[Vec2]| {
+            for (i, point) in points.iter().enumerate() {
+                let p = flip(*point);
+                let _ = writeln!(content, "{:.2} {:.2} {}", p.x, p.y, if i == 0 { "m" } else { "l" });
+            }
+        };
+
+        if let Some(background) = self.background {
+            set_paint(&mut content, background, false);
+            let _ = writeln!(content, "0 0 {:.2} {:.2} re f", self.size.x, height);
+        }
+        for shape in &self.shapes {
+            match shape {
+                FigureShape::Line { from, to, width, color } => {
+                    set_paint(&mut content, *color, true);
+                    let _ = writeln!(content, "{width:.3} w 1 J");
+                    path(&mut content, &[*from, *to]);
+                    content.push_str("S\n");
+                }
+                FigureShape::Polyline { points, width, color } => {
+                    set_paint(&mut content, *color, true);
+                    let _ = writeln!(content, "{width:.3} w 1 J 1 j");
+                    path(&mut content, points);
+                    content.push_str("S\n");
+                }
+                FigureShape::Polygon { points, color } => {
+                    set_paint(&mut content, *color, false);
+                    path(&mut content, points);
+                    content.push_str("h f\n");
+                }
+                FigureShape::Circle { center, radius, color, stroke } => {
+                    set_paint(&mut content, *color, stroke.is_some());
+                    if let Some(width) = stroke {
+                        let _ = writeln!(content, "{width:.3} w");
+                    }
+                    let c = flip(*center);
+                    let (r, k) = (*radius, *radius * BEZIER_CIRCLE);
+                    let _ = writeln!(content, "{:.2} {:.2} m", c.x + r, c.y);
+                    for (d1, d2, end) in [
+                        (Vec2::new(r, k), Vec2::new(k, r), Vec2::new(0.0, r)),
+                        (Vec2::new(-k, r), Vec2::new(-r, k), Vec2::new(-r, 0.0)),
+                        (Vec2::new(-r, -k), Vec2::new(-k, -r), Vec2::new(0.0, -r)),
+                        (Vec2::new(k, -r), Vec2::new(r, -k), Vec2::new(r, 0.0)),
+                    ] {
+                        let (a, b, e) = (c + d1, c + d2, c + end);
+                        let _ = writeln!(content, "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c", a.x, a.y, b.x, b.y, e.x, e.y);
+                    }
+                    content.push_str(if stroke.is_some() { "h S\n" } else { "h f\n" });
+                }
+                FigureShape::Text { position, text, size, color, align, baseline, rotation } => {
+                    set_paint(&mut content, *color, false);
+                    let advance = text.chars().count() as f32 * size * CHAR_WIDTH;
+                    let x = match align {
+                        TextAlign::Start => 0.0,
+                        TextAlign::Middle => -advance / 2.0,
+                        TextAlign::End => -advance,
+                    };
+                    // Start of the baseline relative to `position` in PDF space (y up), then turned
+                    let offset = Vec2::new(x, -baseline_y(0.0, *size, *baseline));
+                    let direction = Vec2::from_angle(*rotation);
+                    let p = flip(*position) + direction.rotate(offset);
+                    let _ = writeln!(
+                        content,
+                        "BT /F1 {size:.2} Tf {:.4} {:.4} {:.4} {:.4} {:.2} {:.2} Tm ({}) Tj ET",
+                        direction.x,
+                        direction.y,
+                        -direction.y,
+                        direction.x,
+                        p.x,
+                        p.y,
+                        escape_pdf(text)
+                    );
+                }
+            }
+        }
+
+        let mut states = String::new();
+        for (level, _) in alphas.iter().enumerate().filter(|(_, used)| **used) {
+            let alpha = level as f32 / 255.0;
+            let _ = write!(states, " /A{level} << /Type /ExtGState /ca {alpha:.4} /CA {alpha:.4} >>");
+        }
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R \
+                 /Resources << /Font << /F1 5 0 R >> /ExtGState <<{states} >> >> >>",
+                self.size.x, height
+            ),
+            format!("<< /Length {} >>\nstream\n{content}endstream", content.len()),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+
+        let mut document = Vec::new();
+        document.extend_from_slice(b"%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(document.len());
+            document.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+        }
+        let xref = document.len();
+        document.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            document.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        document.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n", objects.len() + 1).as_bytes(),
+        );
+        writer.write_all(&document)
+    }
+}
+
+/// Non-linear sRGB channels and alpha of `color`, with HDR colors scaled into range
+fn printable(color: Color) -> ([f32; 3], f32) {
+    let linear = color.to_linear();
+    let peak = linear.red.max(linear.green).max(linear.blue);
+    let scale = if peak > 1.0 { 1.0 / peak } else { 1.0 };
+    let srgb = Color::linear_rgb(linear.red * scale, linear.green * scale, linear.blue * scale).to_srgba();
+    (
+        [srgb.red.clamp(0.0, 1.0), srgb.green.clamp(0.0, 1.0), srgb.blue.clamp(0.0, 1.0)],
+        linear.alpha.clamp(0.0, 1.0),
+    )
+}
+
+fn svg_color(rgb: [f32; 3]) -> String {
+    let [r, g, b] = rgb.map(|c| (c * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn svg_points(points: &[Vec2]) -> String {
+    let mut text = String::with_capacity(points.len() * 16);
+    for point in points {
+        let _ = write!(text, "{:.2},{:.2} ", point.x, point.y);
+    }
+    text.pop();
+    text
+}
+
+/// Baseline of text of `size` placed at `y` by its top, middle or bottom
+fn baseline_y(y: f32, size: f32, baseline: TextBaseline) -> f32 {
+    match baseline {
+        TextBaseline::Top => y + size * CAP_HEIGHT,
+        TextBaseline::Middle => y + size * CAP_HEIGHT / 2.0,
+        TextBaseline::Bottom => y,
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// PDF string literal contents, non-ASCII characters are not in the standard font encoding
+fn escape_pdf(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// Maps world positions to figure pixels through a camera
+#[derive(Clone, Copy)]
+pub struct FigureProjection<'a> {
+    pub camera: &'a Camera,
+    pub transform: &'a GlobalTransform,
+}
+
+impl FigureProjection<'_> {
+    /// Figure position of `world`, `None` when it is behind the camera
+    pub fn point(&self, world: Vec3) -> Option<Vec2> {
+        self.camera.world_to_viewport(self.transform, world).ok()
+    }
+
+    /// Figure length of `length` world units at `world`, measured across the view
+    pub fn length(&self, world: Vec3, length: f32) -> f32 {
+        let right = self.transform.right().as_vec3();
+        match (self.point(world), self.point(world + right * length)) {
+            (Some(a), Some(b)) => a.distance(b),
+            _ => 0.0,
+        }
+    }
+}
+
+/// File format of an exported figure
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FigureFormat {
+    Svg,
+    Pdf,
+}
+
+/// Shape drawn with a `ShapePainter`, in world coordinates
+#[derive(Debug, Clone)]
+enum SceneShape {
+    Line { from: Vec3, to: Vec3, width: f32, color: Color },
+    Polygon { points: Vec<Vec3>, color: Color, stroke: Option<f32> },
+    Circle { center: Vec3, radius: f32, color: Color, stroke: Option<f32> },
+}
+
+/// Figure export requested for this frame.
+/// Draw systems record their `ShapePainter` shapes while `is_capturing`, alongside the painter
+/// calls, and `export_figure` adds them to the ribbons and graphs at the end of the frame.
+#[derive(Resource, Default)]
+pub struct FigureCapture {
+    format: Option<FigureFormat>,
+    shapes: Vec<SceneShape>,
+    /// Figures written so far, numbering the files
+    count: usize,
+}
+
+impl FigureCapture {
+    /// Export a figure at the end of the frame
+    pub fn request(&mut self, format: FigureFormat) {
+        self.format = Some(format);
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.format.is_some()
+    }
+
+    /// Record `painter.line(from, to)`
+    pub fn line(&mut self, painter: &ShapeConfig, from: Vec3, to: Vec3) {
+        if !self.is_capturing() {
+            return;
+        }
+        let transform = painter.transform;
+        self.shapes.push(SceneShape::Line {
+            from: transform.transform_point(from),
+            to: transform.transform_point(to),
+            width: painter.thickness * transform.scale.x,
+            color: painter.color,
+        });
+    }
+
+    /// Record `painter.circle(radius)`
+    pub fn circle(&mut self, painter: &ShapeConfig, radius: f32) {
+        if !self.is_capturing() {
+            return;
+        }
+        let transform = painter.transform;
+        self.shapes.push(SceneShape::Circle {
+            center: transform.translation,
+            radius: radius * transform.scale.x,
+            color: painter.color,
+            stroke: painter.hollow.then_some(painter.thickness * transform.scale.x),
+        });
+    }
+
+    /// Record `painter.rect(size)`, without rounded corners
+    pub fn rect(&mut self, painter: &ShapeConfig, size: Vec2) {
+        if !self.is_capturing() {
+            return;
+        }
+        let transform = painter.transform;
+        let half = size / 2.0;
+        let points = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .map(|(x, y)| transform.transform_point(Vec3::new(x * half.x, y * half.y, 0.0)))
+            .to_vec();
+        self.shapes.push(SceneShape::Polygon {
+            points,
+            color: painter.color,
+            stroke: painter.hollow.then_some(painter.thickness * transform.scale.x),
+        });
+    }
+}
+
+/// System requesting an SVG figure on F9, or a PDF one with Shift+F9
+pub fn request_figure_capture(keys: Res<ButtonInput<KeyCode>>, mut capture: ResMut<FigureCapture>) {
+    if keys.just_pressed(KeyCode::F9) {
+        let pdf = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        capture.request(if pdf { FigureFormat::Pdf } else { FigureFormat::Svg });
+    }
+}
+
+/// Cameras a figure is projected through, and which kind each one is
+type FigureCameraQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Camera, &'static GlobalTransform, Has<Camera2d>, Has<Camera3d>, Has<GraphOverlayCamera>),
+>;
+
+/// Camera kinds a figure is projected through
+#[derive(Clone, Copy, PartialEq)]
+enum FigureCamera {
+    /// `Camera2d` drawing the scene
+    Scene2d,
+    Scene3d,
+    /// Camera of `GraphOverlayPlugin` drawing screen-space graphs
+    GraphOverlay,
+}
+
+/// System writing the requested figure: ribbons, then the recorded scene shapes, then graphs
+/// and color bars on top. Flat ribbons and everything drawn in 2D are projected through the
+/// active `Camera2d`, camera-facing ribbons through the active `Camera3d` and screen-space
+/// graphs through the graph overlay camera.
+/// Files are numbered `figure-001.svg`, `figure-002.pdf`... in the working directory,
+/// on the web they are downloaded by the browser.
+pub fn export_figure(
+    mut capture: ResMut<FigureCapture>,
+    clear_color: Res<ClearColor>,
+    q_camera: FigureCameraQuery,
+    q_ribbon: Query<(&MeshRibbon, &GlobalTransform)>,
+    q_graph: Query<AnyOf<(&GraphWidget, &SpectrumWidget, &HistogramWidget)>>,
+    q_colorbar: Query<&Colorbar>,
+) {
+    let Some(format) = capture.format.take() else {
+        return;
+    };
+    let shapes = std::mem::take(&mut capture.shapes);
+
+    let active = |kind: FigureCamera| {
+        q_camera.iter()
+            .find(|&(camera, _, is_2d, is_3d, is_overlay)| {
+                camera.is_active && match kind {
+                    FigureCamera::Scene2d => is_2d && !is_overlay,
+                    FigureCamera::Scene3d => is_3d,
+                    FigureCamera::GraphOverlay => is_overlay,
+                }
+            })
+            .map(|(camera, transform, ..)| FigureProjection { camera, transform })
+    };
+    let camera_2d = active(FigureCamera::Scene2d);
+    let camera_3d = active(FigureCamera::Scene3d);
+    let overlay = active(FigureCamera::GraphOverlay);
+    let Some(size) = camera_3d.or(camera_2d).and_then(|p| p.camera.logical_viewport_size()) else {
+        log::warn!("No active camera to export a figure from");
+        return;
+    };
+
+    let mut figure = Figure::new(size);
+    figure.background = Some(clear_color.0);
+
+    for (ribbon, transform) in &q_ribbon {
+        let projection = if ribbon.is_billboard() { camera_3d } else { camera_2d };
+        if let Some(projection) = projection {
+            add_ribbon(&mut figure, &projection, ribbon, transform);
+        }
+    }
+
+    if let Some(projection) = camera_2d {
+        for shape in shapes {
+            add_scene_shape(&mut figure, &projection, shape);
+        }
+    }
+    // Plain graphs and the plots of spectrum and histogram widgets
+    let graphs = q_graph.iter().flat_map(|(graph, spectrum, histogram)| {
+        [graph, spectrum.map(|spectrum| &spectrum.graph), histogram.map(|histogram| &histogram.graph)]
+            .into_iter()
+            .flatten()
+    });
+    for graph in graphs {
+        let projection = if graph.params.placement.is_some() { overlay } else { camera_2d };
+        if let Some(projection) = projection {
+            add_graph(&mut figure, &projection, graph);
+        }
+    }
+    if let Some(projection) = camera_2d {
+        for colorbar in &q_colorbar {
+            add_colorbar(&mut figure, &projection, colorbar);
+        }
+    }
+
+    capture.count += 1;
+    let extension = match format {
+        FigureFormat::Svg => "svg",
+        FigureFormat::Pdf => "pdf",
+    };
+    let path = format!("figure-{:03}.{extension}", capture.count);
+    write_figure(&figure, format, &path);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_figure(figure: &Figure, format: FigureFormat, path: &str) {
+    let result = std::fs::File::create(path).map(io::BufWriter::new).and_then(|mut file| {
+        match format {
+            FigureFormat::Svg => figure.write_svg(&mut file)?,
+            FigureFormat::Pdf => figure.write_pdf(&mut file)?,
+        }
+        file.flush()
+    });
+    match result {
+        Ok(()) => log::info!("Figure written to {}", path),
+        Err(error) => log::error!("Failed to write figure {}: {}", path, error),
+    }
+}
+
+/// Hands the figure to the browser as a download named `path`
+#[cfg(target_arch = "wasm32")]
+fn write_figure(figure: &Figure, format: FigureFormat, path: &str) {
+    let mut bytes = Vec::new();
+    let (result, mime) = match format {
+        FigureFormat::Svg => (figure.write_svg(&mut bytes), "image/svg+xml"),
+        FigureFormat::Pdf => (figure.write_pdf(&mut bytes), "application/pdf"),
+    };
+    let result = result
+        .map_err(|error| wasm_bindgen::JsValue::from_str(&error.to_string()))
+        .and_then(|()| download_bytes(&bytes, path, mime));
+    match result {
+        Ok(()) => log::info!("Figure downloaded as {}", path),
+        Err(error) => log::error!("Failed to download figure {}: {:?}", path, error),
+    }
+}
+
+/// Time the browser gets to start a download before its object URL is revoked
+#[cfg(target_arch = "wasm32")]
+const REVOKE_DELAY_MS: i32 = 1000;
+
+/// Hands `bytes` of type `mime` to the browser as a download named `name`
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn download_bytes(bytes: &[u8], name: &str, mime: &str) -> Result<(), wasm_bindgen::JsValue> {
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes).into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+    let document = window.document().ok_or_else(|| JsValue::from_str("No document"))?;
+    let link = document.create_element("a")?;
+    link.set_attribute("href", &url)?;
+    link.set_attribute("download", name)?;
+    link.dyn_into::<web_sys::HtmlElement>()?.click();
+    // The browser starts the download after the click returns, revoking the URL right away can cancel it
+    let revoke = Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window.set_timeout_with_callback_and_timeout_and_arguments_0(revoke.unchecked_ref(), REVOKE_DELAY_MS)?;
+    Ok(())
+}
+
+/// Add the drawn `ribbon` to `figure` as one quad per resampled segment, with the width,
+/// color and fade of the shader. `projection` is the camera drawing the ribbon and
+/// `transform` the ribbon entity's.
+fn add_ribbon(figure: &mut Figure, projection: &FigureProjection, ribbon: &MeshRibbon, transform: &GlobalTransform) {
+    let settings = ribbon.settings();
+    let points = ribbon.resample_drawn(0);
+    // Figure position, half width and color of every point, `None` behind the camera
+    let projected: Vec<Option<(Vec2, f32, Color)>> = points.iter()
+        .map(|point| {
+            let world = transform.transform_point(point.position);
+            let (half_width, color) = settings.vertex_style(point.birth, point.arc_length, point.scalar);
+            let color = Color::linear_rgba(color.x, color.y, color.z, color.w);
+            let center = projection.point(world)?;
+            Some((center, projection.length(world, half_width * transform.scale().x), color))
+        })
+        .collect();
+
+    let center = |i: usize| projected.get(i).copied().flatten().map(|(c, ..)| c);
+    // Side of each point in the figure, across the direction between its neighbours
+    let side = |i: usize| {
+        let previous = i.checked_sub(1).and_then(center).or(center(i))?;
+        let next = center(i + 1).or(center(i))?;
+        (next - previous).try_normalize().map(|d| d.perp())
+    };
+
+    let mut last_side = Vec2::Y;
+    let mut previous: Option<(Vec2, Vec2, Color)> = None;
+    for (i, point) in projected.iter().enumerate() {
+        let Some((c, half_width, color)) = *point else {
+            previous = None;
+            continue;
+        };
+        last_side = side(i).unwrap_or(last_side);
+        let offset = last_side * half_width;
+        if let Some((left, right, previous_color)) = previous {
+            let color = Color::from(previous_color.to_linear().mix(&color.to_linear(), 0.5));
+            if color.alpha() > 0.0 && (left != right || offset != Vec2::ZERO) {
+                figure.push(FigureShape::Polygon { points: vec![left, right, c - offset, c + offset], color });
+            }
+        }
+        previous = Some((c + offset, c - offset, color));
+    }
+}
+
+/// Add `graph` to `figure` as drawn on screen, `projection` being the camera drawing it
+fn add_graph(figure: &mut Figure, projection: &FigureProjection, graph: &GraphWidget) {
+    let pixels = projection.length(graph.params.position.extend(0.0), 1.0);
+    let project = |p: Vec2| projection.point(p.extend(0.0));
+    let layout = graph.layout();
+
+    for &(from, to) in &layout.gridlines {
+        if let (Some(from), Some(to)) = (project(from), project(to)) {
+            figure.push(FigureShape::Line {
+                from,
+                to,
+                width: GRID_THICKNESS * pixels,
+                color: graph.params.grid_color,
+            });
+        }
+    }
+
+    for &(from, to) in &layout.crosshair {
+        if let (Some(from), Some(to)) = (project(from), project(to)) {
+            figure.push(FigureShape::Line {
+                from,
+                to,
+                width: GRID_THICKNESS * pixels,
+                color: graph.params.text_color,
+            });
+        }
+    }
+
+    for &(mark, color) in &layout.marks {
+        match mark {
+            SeriesMark::Segment(from, to) => {
+                if let (Some(from), Some(to)) = (project(from), project(to)) {
+                    figure.push(FigureShape::Line { from, to, width: LINE_THICKNESS * pixels, color });
+                }
+            }
+            SeriesMark::Dot(center, radius) => {
+                if let Some(center) = project(center) {
+                    figure.push(FigureShape::Circle { center, radius: radius * pixels, color, stroke: None });
+                }
+            }
+        }
+    }
+
+    for label in layout.labels {
+        if let Some(position) = project(label.position) {
+            let text = FigureShape::text(position, label.text, label.font_size * pixels, label.color, label.anchor);
+            figure.push(text.rotated(label.rotation));
+        }
+    }
+}
+
+/// Add the bar of `colorbar`, as one band per texel, and its labels to `figure`
+fn add_colorbar(figure: &mut Figure, projection: &FigureProjection, colorbar: &Colorbar) {
+    let Some(colormap) = &colorbar.colormap else {
+        return;
+    };
+    let pos = colorbar.params.position;
+    let size = colorbar.params.size;
+    let project = |x: f32, y: f32| projection.point(Vec3::new(x, y, 0.0));
+
+    let last = (COLORBAR_RESOLUTION - 1) as f32;
+    let band = size.y / COLORBAR_RESOLUTION as f32;
+    for row in 0..COLORBAR_RESOLUTION {
+        let top = pos.y - band * row as f32;
+        // Bands overlap slightly so no seams show between them
+        let bottom = top - band * 1.05;
+        let corners = [(pos.x, top), (pos.x + size.x, top), (pos.x + size.x, bottom), (pos.x, bottom)];
+        if let Some(points) = corners.iter().map(|&(x, y)| project(x, y)).collect::<Option<Vec<_>>>() {
+            let color = colormap.sample(1.0 - row as f32 / last);
+            figure.push(FigureShape::Polygon { points, color });
+        }
+    }
+
+    let font_size = colorbar.params.font_size * projection.length(pos.extend(0.0), 1.0);
+    let text_color = colorbar.params.text_color;
+    if let Some(position) = project(pos.x, pos.y + 5.0) {
+        figure.push(FigureShape::text(position, &colorbar.params.label, font_size, text_color, Anchor::BOTTOM_LEFT));
+    }
+    let num_ticks = colorbar.tick_texts.len();
+    for i in 0..num_ticks {
+        let t = i as f32 / num_ticks.saturating_sub(1).max(1) as f32;
+        let value = colorbar.range.0 + (colorbar.range.1 - colorbar.range.0) * t;
+        if let Some(position) = project(pos.x + size.x + 4.0, pos.y - size.y + size.y * t) {
+            let text = format!("{:.2}", value);
+            figure.push(FigureShape::text(position, text, font_size, text_color, Anchor::CENTER_LEFT));
+        }
+    }
+}
+
+fn add_scene_shape(figure: &mut Figure, projection: &FigureProjection, shape: SceneShape) {
+    match shape {
+        SceneShape::Line { from, to, width, color } => {
+            if let (Some(a), Some(b)) = (projection.point(from), projection.point(to)) {
+                let width = projection.length(from, width);
+                figure.push(FigureShape::Line { from: a, to: b, width, color });
+            }
+        }
+        SceneShape::Polygon { points, color, stroke } => {
+            let Some(mut projected) = points.iter().map(|p| projection.point(*p)).collect::<Option<Vec<_>>>() else {
+                return;
+            };
+            match stroke {
+                Some(width) => {
+                    projected.push(projected[0]);
+                    let width = projection.length(points[0], width);
+                    figure.push(FigureShape::Polyline { points: projected, width, color });
+                }
+                None => figure.push(FigureShape::Polygon { points: projected, color }),
+            }
+        }
+        SceneShape::Circle { center, radius, color, stroke } => {
+            if let Some(c) = projection.point(center) {
+                figure.push(FigureShape::Circle {
+                    center: c,
+                    radius: projection.length(center, radius),
+                    color,
+                    stroke: stroke.map(|width| projection.length(center, width)),
+                });
+            }
+        }
+    }
+}
+
+/// Exports the scene as a vector figure on F9 (SVG) or Shift+F9 (PDF).
+/// Painter shapes only appear when their draw system records them in `FigureCapture`.
+pub struct FigureExportPlugin;
+
+impl Plugin for FigureExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FigureCapture>()
+            .add_systems(PreUpdate, request_figure_capture.after(bevy::input::InputSystems))
+            .add_systems(PostUpdate, export_figure.after(TransformSystems::Propagate));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One shape of each kind, half transparent polygon and glowing line
+    fn sample_figure() -> Figure {
+        let mut figure = Figure::new(Vec2::new(200.0, 100.0));
+        figure.background = Some(Color::BLACK);
+        figure.push(FigureShape::Line {
+            from: Vec2::new(10.0, 10.0),
+            to: Vec2::new(190.0, 90.0),
+            width: 2.0,
+            color: Color::linear_rgb(4.0, 2.0, 0.0),
+        });
+        figure.push(FigureShape::Polyline {
+            points: vec![Vec2::ZERO, Vec2::new(50.0, 20.0), Vec2::new(100.0, 0.0)],
+            width: 1.0,
+            color: Color::WHITE,
+        });
+        figure.push(FigureShape::Polygon {
+            points: vec![Vec2::new(20.0, 20.0), Vec2::new(60.0, 20.0), Vec2::new(40.0, 60.0)],
+            color: Color::linear_rgba(0.0, 0.0, 1.0, 0.5),
+        });
+        figure.push(FigureShape::Circle { center: Vec2::new(150.0, 50.0), radius: 10.0, color: Color::WHITE, stroke: None });
+        figure.push(FigureShape::Circle { center: Vec2::new(150.0, 50.0), radius: 20.0, color: Color::WHITE, stroke: Some(1.5) });
+        figure.push(FigureShape::text(Vec2::new(100.0, 50.0), "a<b & (c) \\ é", 12.0, Color::WHITE, Anchor::CENTER));
+        figure
+    }
+
+    #[test]
+    fn svg_holds_every_shape() {
+        let mut bytes = Vec::new();
+        sample_figure().write_svg(&mut bytes).unwrap();
+        let svg = String::from_utf8(bytes).unwrap();
+
+        assert!(svg.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(svg.contains(r#"width="200" height="100" viewBox="0 0 200.00 100.00""#));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<line ").count(), 1);
+        assert_eq!(svg.matches("<polyline ").count(), 1);
+        assert_eq!(svg.matches("<circle ").count(), 2);
+        assert!(svg.contains(r##"<polygon points="20.00,20.00 60.00,20.00 40.00,60.00" fill="#0000ff" fill-opacity="0.500"/>"##));
+        // Glow is scaled back into range keeping the hue
+        assert!(svg.contains(r##"stroke="#ffbc00""##), "{}", svg);
+        assert!(svg.contains(r##"r="20.000" fill="none" stroke="#ffffff""##));
+        assert!(svg.contains(">a&lt;b &amp; (c) \\ é</text>"));
+    }
+
+    /// Every `N 0 obj` the xref table points at, and the `startxref` offset
+    fn pdf_offsets(pdf: &[u8]) -> (Vec<usize>, usize) {
+        let text = String::from_utf8_lossy(pdf);
+        let xref = text.find("\nxref\n").unwrap();
+        let offsets = text[xref..]
+            .lines()
+            .filter(|line| line.ends_with(" 00000 n "))
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        let start = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        (offsets, start)
+    }
+
+    #[test]
+    fn pdf_cross_reference_points_at_objects() {
+        let mut pdf = Vec::new();
+        sample_figure().write_pdf(&mut pdf).unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+
+        let (offsets, start) = pdf_offsets(&pdf);
+        assert_eq!(offsets.len(), 5);
+        for (i, &offset) in offsets.iter().enumerate() {
+            let header = format!("{} 0 obj\n", i + 1);
+            assert!(pdf[offset..].starts_with(header.as_bytes()), "object {} not at {}", i + 1, offset);
+        }
+        assert!(pdf[start..].starts_with(b"xref\n"));
+    }
+
+    #[test]
+    fn pdf_stream_length_matches() {
+        let mut pdf = Vec::new();
+        sample_figure().write_pdf(&mut pdf).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        let length: usize = text.split("/Length ").nth(1).unwrap().split(' ').next().unwrap().parse().unwrap();
+        let start = text.find(">>\nstream\n").unwrap() + ">>\nstream\n".len();
+        let end = text.find("endstream").unwrap();
+        assert_eq!(end - start, length);
+    }
+
+    #[test]
+    fn pdf_alpha_uses_graphics_states() {
+        let mut pdf = Vec::new();
+        sample_figure().write_pdf(&mut pdf).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+
+        // Every state set in the content is defined in the page resources
+        let used: Vec<&str> = text.split_whitespace().collect::<Vec<_>>()
+            .windows(2)
+            .filter(|pair| pair[1] == "gs")
+            .map(|pair| pair[0])
+            .collect();
+        assert!(used.contains(&"/A128") && used.contains(&"/A255"));
+        for state in used {
+            assert!(text.contains(&format!("{state} << /Type /ExtGState")), "{} undefined", state);
+        }
+        assert!(text.contains("/A128 << /Type /ExtGState /ca 0.5020 /CA 0.5020 >>"));
+    }
+
+    #[test]
+    fn pdf_text_is_escaped() {
+        let mut pdf = Vec::new();
+        sample_figure().write_pdf(&mut pdf).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(r"(a<b & \(c\) \\ ?) Tj"), "{}", text);
+    }
+}
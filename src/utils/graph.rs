@@ -1,7 +1,28 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::visibility::{NoFrustumCulling, RenderLayers};
+use bevy::ecs::system::SystemParam;
+use bevy::mesh::{Indices, PrimitiveTopology};
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy_vector_shapes::prelude::*;
 use std::collections::VecDeque;
+use std::io::{self, Write};
+
+use crate::utils::decimation::min_max_columns;
+use crate::utils::graph_export::GRAPH_EXPORT_LAYER;
+use crate::utils::graph_overlay::{GraphPlacement, GRAPH_OVERLAY_LAYER};
+
+/// Thickness of the gridlines
+pub(crate) const GRID_THICKNESS: f32 = 0.25;
+
+/// Thickness of the data line
+pub(crate) const LINE_THICKNESS: f32 = 2.0;
+
+/// Width of the columns lines are decimated into, one pixel of a screen-space graph
+const DECIMATION_COLUMN: f32 = 1.0;
+
+/// Gridlines drawn at most along each axis, guards against spacings far too small for the range
+const MAX_GRIDLINES: usize = 64;
 
 #[derive(Clone)]
 pub struct GraphParams {
@@ -9,6 +30,8 @@ pub struct GraphParams {
     pub position: Vec2,
     /// Size of the graph widget
     pub size: Vec2,
+    /// Screen-space placement overriding `position` and `size`, `None` draws the graph in world space
+    pub placement: Option<GraphPlacement>,
     /// Maximum number of data points to display
     pub max_points: usize,
     /// Color of the graph line
@@ -21,12 +44,23 @@ pub struct GraphParams {
     pub y_gridlines: GridlineConfig,
     /// Origin point for gridline alignment (gridlines will be multiples of this)
     pub gridline_origin: Vec2,
+    /// How the x and y ranges follow the data
+    pub x_range: RangeMode,
+    pub y_range: RangeMode,
+    /// Linear or logarithmic axes
+    pub x_scale: AxisScale,
+    pub y_scale: AxisScale,
     /// Distance threshold from edge to trigger expansion (as fraction of range, e.g., 0.1 = 10%)
     pub expansion_threshold: f32,
-    /// Minimum y-range to prevent division by zero
+    /// Minimum y-range to prevent division by zero, in decades on a log axis
     pub min_y_range: f32,
+    /// Keep the y range symmetric around 0, for signed quantities like angles or drifts
+    pub symmetric_y: bool,
     /// Label for the graph
     pub label: String,
+    /// Axis titles with their units, e.g. "t (s)". Empty titles are not drawn.
+    pub x_title: String,
+    pub y_title: String,
     /// Show current values in top right
     pub show_current_x: bool,
     pub show_current_y: bool,
@@ -34,26 +68,235 @@ pub struct GraphParams {
     pub text_color: Color,
     /// Font size for labels
     pub font_size: f32,
+    /// Legend of the series names (top left inside the plot), drawn when there is more than one series
+    pub show_legend: bool,
+    /// How the series are drawn
+    pub backend: GraphBackend,
+}
+
+/// Renderer of the series of a graph
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GraphBackend {
+    /// One painter shape per segment or dot
+    #[default]
+    Painter,
+    /// A single mesh per graph rewritten every frame, for long histories
+    Mesh,
 }
 
 #[derive(Clone)]
 pub enum GridlineConfig {
     /// Fixed spacing between gridlines (in data units)
     Fixed { spacing: f32 },
-    /// Dynamic spacing based on data range
+    /// Spacing of 1, 2 or 5 × 10^k data units giving about `num_lines` gridlines over the range
     Dynamic {
-        /// Minimum spacing between gridlines (in data units)
+        /// Minimum spacing between gridlines (in data units), rounded up to a nice number
         min_spacing: f32,
         /// Number of gridlines to target
         num_lines: usize,
     },
 }
 
+/// How an axis range follows the data. Ranges are worked out along the axis as drawn,
+/// so in decades on a log axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RangeMode {
+    /// Exactly the extent of the data
+    Data,
+    /// Constant range, in data units
+    Fixed { min: f32, max: f32 },
+    /// The last `span` data units up to the newest value, e.g. a time window
+    SlidingWindow { span: f32 },
+    /// Grows past the data when it comes within `expansion_threshold` of an edge, never shrinks
+    Expand,
+    /// Grows like `Expand`, and fits the data again once it spans less than `shrink_below` of
+    /// the range. The gap between both thresholds keeps the range from flickering.
+    Autoscale { shrink_below: f32 },
+}
+
+impl RangeMode {
+    /// Range along the drawn axis from the `current` one and the `data` bounds
+    fn apply(self, scale: AxisScale, current: (f32, f32), data: (f32, f32), threshold: f32) -> (f32, f32) {
+        let expand = |(mut min, mut max): (f32, f32)| {
+            if !min.is_finite() || !max.is_finite() {
+                return data;
+            }
+            let threshold_distance = (max - min) * threshold;
+            // Check if we need to expand upward
+            if data.1 > max - threshold_distance {
+                max = data.1 + threshold_distance;
+            }
+            // Check if we need to expand downward
+            if data.0 < min + threshold_distance {
+                min = data.0 - threshold_distance;
+            }
+            (min, max)
+        };
+        match self {
+            RangeMode::Data => data,
+            RangeMode::Fixed { min, max } => (scale.forward(min), scale.forward(max)),
+            RangeMode::SlidingWindow { span } => {
+                let min = scale.forward(scale.inverse(data.1) - span);
+                (if min.is_finite() { min } else { data.0 }, data.1)
+            }
+            RangeMode::Expand => expand(current),
+            RangeMode::Autoscale { shrink_below } => {
+                let (min, max) = expand(current);
+                if data.1 - data.0 < shrink_below * (max - min) {
+                    let margin = (data.1 - data.0) * threshold;
+                    (data.0 - margin, data.1 + margin)
+                } else {
+                    (min, max)
+                }
+            }
+        }
+    }
+}
+
+/// Mapping of data values along an axis
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AxisScale {
+    #[default]
+    Linear,
+    /// Base 10 logarithm, values at or below 0 are left out
+    Log,
+}
+
+impl AxisScale {
+    /// Position along the drawn axis of `value`, NaN when it has none
+    pub fn forward(self, value: f32) -> f32 {
+        match self {
+            AxisScale::Linear => value,
+            AxisScale::Log if value > 0.0 => value.log10(),
+            AxisScale::Log => f32::NAN,
+        }
+    }
+
+    /// Value at the position `position` along the drawn axis
+    pub fn inverse(self, position: f32) -> f32 {
+        match self {
+            AxisScale::Linear => position,
+            AxisScale::Log => 10f32.powf(position),
+        }
+    }
+}
+
+/// How a series is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeriesStyle {
+    Line,
+    /// Dashes and gaps of the given lengths along the line, in pixels
+    Dashed { dash: f32, gap: f32 },
+    /// A dot of `radius` pixels at every data point
+    Points { radius: f32 },
+}
+
+/// Named data series of a graph
+#[derive(Clone)]
+pub struct GraphSeries {
+    pub name: String,
+    pub color: Color,
+    pub style: SeriesStyle,
+    /// Hidden series are left out of the plot and the axis ranges, the legend still lists them
+    pub visible: bool,
+    /// Data points stored as (x, y)
+    pub data: VecDeque<(f32, f32)>,
+}
+
+impl GraphSeries {
+    pub fn new(name: impl Into<String>, color: Color) -> Self {
+        Self {
+            name: name.into(),
+            color,
+            style: SeriesStyle::Line,
+            visible: true,
+            data: VecDeque::new(),
+        }
+    }
+
+    pub fn with_style(mut self, style: SeriesStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+/// Point along the drawn axes, `None` off a log axis
+fn scaled_point((x_scale, y_scale): (AxisScale, AxisScale), x: f32, y: f32) -> Option<Vec2> {
+    let point = Vec2::new(x_scale.forward(x), y_scale.forward(y));
+    point.is_finite().then_some(point)
+}
+
+/// Bounds of a series along the drawn axes while points come in at the back and leave at the front.
+/// Each extreme is a monotonic deque of (sequence number, value): the front is the extreme of the
+/// kept points, later points that beat earlier ones drop them, so a point costs amortized O(1).
+#[derive(Clone, Default)]
+struct SeriesBounds {
+    /// Sequence number of the next point added
+    next: u64,
+    /// Sequence number of the oldest point kept
+    first: u64,
+    /// Increasing minima and decreasing maxima along each axis
+    min_x: VecDeque<(u64, f32)>,
+    max_x: VecDeque<(u64, f32)>,
+    min_y: VecDeque<(u64, f32)>,
+    max_y: VecDeque<(u64, f32)>,
+}
+
+impl SeriesBounds {
+    fn new(series: &GraphSeries, scales: (AxisScale, AxisScale)) -> Self {
+        let mut bounds = Self::default();
+        for &(x, y) in &series.data {
+            bounds.push_back(scales, x, y);
+        }
+        bounds
+    }
+
+    /// Add the point pushed at the back of the series, points off a log axis are left out
+    fn push_back(&mut self, scales: (AxisScale, AxisScale), x: f32, y: f32) {
+        let index = self.next;
+        self.next += 1;
+        let Some(point) = scaled_point(scales, x, y) else {
+            return;
+        };
+        push_extreme(&mut self.min_x, index, point.x, |kept, value| kept < value);
+        push_extreme(&mut self.max_x, index, point.x, |kept, value| kept > value);
+        push_extreme(&mut self.min_y, index, point.y, |kept, value| kept < value);
+        push_extreme(&mut self.max_y, index, point.y, |kept, value| kept > value);
+    }
+
+    /// Forget the point popped from the front of the series
+    fn pop_front(&mut self) {
+        self.first += 1;
+        for extremes in [&mut self.min_x, &mut self.max_x, &mut self.min_y, &mut self.max_y] {
+            while extremes.front().is_some_and(|&(index, _)| index < self.first) {
+                extremes.pop_front();
+            }
+        }
+    }
+
+    fn rect(&self) -> Option<Rect> {
+        Some(Rect {
+            min: Vec2::new(self.min_x.front()?.1, self.min_y.front()?.1),
+            max: Vec2::new(self.max_x.front()?.1, self.max_y.front()?.1),
+        })
+    }
+}
+
+/// Push `value` on a monotonic deque, dropping the values it beats. `keeps(kept, value)` tells whether
+/// an earlier value stays an extreme candidate next to the new one.
+fn push_extreme(extremes: &mut VecDeque<(u64, f32)>, index: u64, value: f32, keeps: impl Fn(f32, f32) -> bool) {
+    while extremes.back().is_some_and(|&(_, kept)| !keeps(kept, value)) {
+        extremes.pop_back();
+    }
+    extremes.push_back((index, value));
+}
+
 impl Default for GraphParams {
     fn default() -> Self {
         Self {
             position: Vec2::new(-400.0, 300.0),
             size: Vec2::new(300.0, 200.0),
+            placement: None,
             max_points: 200,
             line_color: Color::linear_rgba(3.0, 0.6, 0.2, 1.0),
             grid_color: Color::srgba(0.5, 0.5, 0.5, 0.5),
@@ -63,111 +306,244 @@ impl Default for GraphParams {
                 num_lines: 4,
             },
             gridline_origin: Vec2::ZERO,
+            x_range: RangeMode::Data,
+            y_range: RangeMode::Expand,
+            x_scale: AxisScale::Linear,
+            y_scale: AxisScale::Linear,
             expansion_threshold: 0.1,
             min_y_range: 0.1,
+            symmetric_y: false,
             label: "Graph".to_string(),
+            x_title: String::new(),
+            y_title: String::new(),
             show_current_x: false,
             show_current_y: true,
             text_color: Color::srgba(0.9, 0.9, 0.9, 1.0),
             font_size: 12.0,
+            show_legend: true,
+            backend: GraphBackend::Painter,
         }
     }
 }
 
+/// Labels are children of the widget, laid out in world coordinates under an identity transform
 #[derive(Component)]
+#[require(Transform, Visibility)]
 pub struct GraphWidget {
     pub params: GraphParams,
-    /// Data points stored as (time, value)
-    pub data: VecDeque<(f32, f32)>,
+    /// Series sharing the axes, `add_point` feeds the first one
+    pub series: Vec<GraphSeries>,
     /// Current axis ranges
     pub x_min: f32,
     pub x_max: f32,
     pub y_min: f32,
     pub y_max: f32,
-    /// Text entity handles for cleanup and updates
-    pub text_entities: Vec<Entity>,
+    /// Ranges set by zooming or panning, shown instead of the current ones until reset
+    pub view: Option<Rect>,
+    /// Frozen graphs ignore new points, so the plot holds still while the simulation runs
+    pub frozen: bool,
+    /// Cursor over the plot area, in world coordinates, for the hover crosshair
+    pub(crate) cursor: Option<Vec2>,
+    /// Being captured by `GraphExportPlugin`, drawn on the export layer as well and without the crosshair
+    pub(crate) exporting: bool,
+    /// Text entities showing `layout().labels`
+    pub(crate) labels: LabelPool,
+    /// Mesh entity showing the series with `GraphBackend::Mesh`
+    pub(crate) series_mesh: SeriesMesh,
+    /// Bounds of each series along the drawn axes, kept up to date by `add_series_point`
+    /// so a new point does not rescan the whole history
+    bounds: Vec<SeriesBounds>,
+    /// Axis scales `bounds` were taken with
+    bounds_scales: (AxisScale, AxisScale),
 }
 
 impl GraphWidget {
+    /// Graph with a single series drawn in `line_color` and named after `label`
     pub fn new(params: GraphParams) -> Self {
+        let series = GraphSeries::new(params.label.clone(), params.line_color);
+        Self::with_series(params, vec![series])
+    }
+
+    /// Graph plotting `series` on shared axes
+    pub fn with_series(params: GraphParams, series: Vec<GraphSeries>) -> Self {
         Self {
             params,
-            data: VecDeque::new(),
+            series,
             x_min: 0.0,
             x_max: 10.0,
             y_min: -1.0,
             y_max: 1.0,
-            text_entities: Vec::new(),
+            view: None,
+            frozen: false,
+            cursor: None,
+            exporting: false,
+            labels: LabelPool::default(),
+            series_mesh: SeriesMesh::default(),
+            bounds: Vec::new(),
+            bounds_scales: (AxisScale::Linear, AxisScale::Linear),
         }
     }
 
-    /// Add a new data point (time, value)
+    /// Add a new data point (time, value) to the first series
     pub fn add_point(&mut self, time: f32, value: f32) {
-        self.data.push_back((time, value));
-        
+        self.add_series_point(0, time, value);
+    }
+
+    /// Add a series on the same axes, returns its index for `add_series_point`
+    pub fn add_series(&mut self, series: GraphSeries) -> usize {
+        self.series.push(series);
+        self.series.len() - 1
+    }
+
+    /// Index of the series called `name`
+    pub fn series_index(&self, name: &str) -> Option<usize> {
+        self.series.iter().position(|series| series.name == name)
+    }
+
+    /// Add a new data point to the series at `index`
+    pub fn add_series_point(&mut self, index: usize, x: f32, y: f32) {
+        if self.frozen {
+            return;
+        }
+        let max_points = self.params.max_points;
+        if index >= self.series.len() {
+            return;
+        }
+        // Bounds taken before the new point, so a rescan does not count it twice
+        self.refresh_bounds();
+        let scales = self.bounds_scales;
+        let series = &mut self.series[index];
+        let bounds = &mut self.bounds[index];
+        series.data.push_back((x, y));
+        bounds.push_back(scales, x, y);
+
         // Remove old points
-        if self.data.len() > self.params.max_points {
-            self.data.pop_front();
+        if series.data.len() > max_points {
+            series.data.pop_front();
+            bounds.pop_front();
         }
 
         // Update axis ranges
         self.update_ranges();
     }
 
-    fn update_ranges(&mut self) {
-        if self.data.is_empty() {
-            return;
+    /// Show or hide the series called `name`
+    pub fn set_series_visible(&mut self, name: &str, visible: bool) {
+        if let Some(index) = self.series_index(name) {
+            self.series[index].visible = visible;
+            // Widgets like the histogram rewrite their data in place, take fresh bounds
+            self.bounds.clear();
+            self.update_ranges();
         }
+    }
 
-        // Get current data bounds
-        let (mut data_x_min, mut data_x_max) = (f32::MAX, f32::MIN);
-        let (mut data_y_min, mut data_y_max) = (f32::MAX, f32::MIN);
+    /// Layers the graph is drawn on, the overlay camera's for screen-space graphs,
+    /// plus the export layer while it is captured
+    pub fn render_layers(&self) -> RenderLayers {
+        let layers = match self.params.placement {
+            Some(_) => RenderLayers::layer(GRAPH_OVERLAY_LAYER),
+            None => RenderLayers::default(),
+        };
+        if self.exporting { layers.with(GRAPH_EXPORT_LAYER) } else { layers }
+    }
 
-        for &(x, y) in &self.data {
-            data_x_min = data_x_min.min(x);
-            data_x_max = data_x_max.max(x);
-            data_y_min = data_y_min.min(y);
-            data_y_max = data_y_max.max(y);
+    /// Rescan every series if the series or the axis scales changed since the bounds were taken.
+    /// Code editing `GraphSeries::data` directly sets the axis ranges itself.
+    fn refresh_bounds(&mut self) {
+        let scales = (self.params.x_scale, self.params.y_scale);
+        if self.bounds.len() != self.series.len() || self.bounds_scales != scales {
+            self.bounds_scales = scales;
+            self.bounds = self.series.iter().map(|series| SeriesBounds::new(series, scales)).collect();
         }
+    }
 
-        // X-axis: sliding window (always show most recent data)
-        self.x_max = data_x_max;
-        self.x_min = data_x_min;
+    fn update_ranges(&mut self) {
+        self.refresh_bounds();
+        let (x_scale, y_scale) = self.bounds_scales;
+
+        // Get current data bounds along the drawn axes, points off a log axis are left out
+        let bounds = self.series.iter()
+            .zip(&self.bounds)
+            .filter(|(series, _)| series.visible)
+            .filter_map(|(_, bounds)| bounds.rect())
+            .reduce(|a, b| a.union(b));
+        let Some(bounds) = bounds else {
+            return;
+        };
 
-        // Y-axis: expand when data approaches boundaries
-        let y_range = self.y_max - self.y_min;
-        let threshold_distance = y_range * self.params.expansion_threshold;
+        let threshold = self.params.expansion_threshold;
+        let (x_min, x_max) = self.params.x_range.apply(
+            x_scale,
+            (x_scale.forward(self.x_min), x_scale.forward(self.x_max)),
+            (bounds.min.x, bounds.max.x),
+            threshold,
+        );
+        let (mut y_min, mut y_max) = self.params.y_range.apply(
+            y_scale,
+            (y_scale.forward(self.y_min), y_scale.forward(self.y_max)),
+            (bounds.min.y, bounds.max.y),
+            threshold,
+        );
 
-        // Check if we need to expand upward
-        if data_y_max > self.y_max - threshold_distance {
-            self.y_max = data_y_max + threshold_distance;
+        // Ensure minimum range
+        if y_max - y_min < self.params.min_y_range {
+            let center = (y_max + y_min) / 2.0;
+            y_max = center + self.params.min_y_range / 2.0;
+            y_min = center - self.params.min_y_range / 2.0;
         }
 
-        // Check if we need to expand downward
-        if data_y_min < self.y_min + threshold_distance {
-            self.y_min = data_y_min - threshold_distance;
+        if self.params.symmetric_y && y_scale == AxisScale::Linear {
+            let extent = y_max.abs().max(y_min.abs());
+            y_max = extent;
+            y_min = -extent;
         }
 
-        // Ensure minimum range
-        if self.y_max - self.y_min < self.params.min_y_range {
-            let center = (self.y_max + self.y_min) / 2.0;
-            self.y_max = center + self.params.min_y_range / 2.0;
-            self.y_min = center - self.params.min_y_range / 2.0;
+        self.x_min = x_scale.inverse(x_min);
+        self.x_max = x_scale.inverse(x_max);
+        self.y_min = y_scale.inverse(y_min);
+        self.y_max = y_scale.inverse(y_max);
+    }
+
+    /// Ranges shown on the axes: the zoomed or panned view if any, else the current ranges
+    pub fn data_range(&self) -> Rect {
+        self.view.unwrap_or(Rect {
+            min: Vec2::new(self.x_min, self.y_min),
+            max: Vec2::new(self.x_max, self.y_max),
+        })
+    }
+
+    /// Shown ranges along the drawn axes, in decades on a log axis
+    fn scaled_range(&self) -> Rect {
+        let range = self.data_range();
+        let (x_scale, y_scale) = (self.params.x_scale, self.params.y_scale);
+        Rect {
+            min: Vec2::new(x_scale.forward(range.min.x), y_scale.forward(range.min.y)),
+            max: Vec2::new(x_scale.forward(range.max.x), y_scale.forward(range.max.y)),
         }
     }
 
-    /// Convert data coordinates to screen coordinates
-    fn to_screen(&self, x: f32, y: f32) -> Vec2 {
-        let x_range = self.x_max - self.x_min;
-        let y_range = self.y_max - self.y_min;
+    /// Show the ranges `scaled`, given along the drawn axes
+    fn set_scaled_view(&mut self, scaled: Rect) {
+        let (x_scale, y_scale) = (self.params.x_scale, self.params.y_scale);
+        self.view = Some(Rect {
+            min: Vec2::new(x_scale.inverse(scaled.min.x), y_scale.inverse(scaled.min.y)),
+            max: Vec2::new(x_scale.inverse(scaled.max.x), y_scale.inverse(scaled.max.y)),
+        });
+    }
+
+    /// Convert data coordinates to screen coordinates, NaN for values off a log axis
+    pub fn to_screen(&self, x: f32, y: f32) -> Vec2 {
+        let range = self.scaled_range();
+        let x_range = range.max.x - range.min.x;
+        let y_range = range.max.y - range.min.y;
 
         let x_normalized = if x_range > 0.0 {
-            (x - self.x_min) / x_range
+            (self.params.x_scale.forward(x) - range.min.x) / x_range
         } else {
             0.5
         };
         let y_normalized = if y_range > 0.0 {
-            (y - self.y_min) / y_range
+            (self.params.y_scale.forward(y) - range.min.y) / y_range
         } else {
             0.5
         };
@@ -177,186 +553,708 @@ impl GraphWidget {
             self.params.position.y - y_normalized * self.params.size.y,
         )
     }
+
+    /// Convert screen coordinates to data coordinates, the inverse of `to_screen`
+    pub fn from_screen(&self, point: Vec2) -> Vec2 {
+        let range = self.scaled_range();
+        let scaled = range.min + self.normalized(point) * range.size();
+        Vec2::new(self.params.x_scale.inverse(scaled.x), self.params.y_scale.inverse(scaled.y))
+    }
+
+    /// Position of the screen point `point` across the plot area, 0 to 1 along each axis
+    fn normalized(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            (point.x - self.params.position.x) / self.params.size.x,
+            (self.params.position.y - point.y) / self.params.size.y,
+        )
+    }
+
+    /// Whether the screen point `point` is over the plot area
+    pub fn contains(&self, point: Vec2) -> bool {
+        let pos = self.params.position;
+        let size = self.params.size;
+        (pos.x..=pos.x + size.x).contains(&point.x) && (pos.y - size.y..=pos.y).contains(&point.y)
+    }
+
+    /// Scale the shown ranges by `factor` around the data point `center`, values below 1 zoom in.
+    /// Log axes zoom by the same factor in decades.
+    pub fn zoom(&mut self, center: Vec2, factor: Vec2) {
+        let range = self.scaled_range();
+        let center = Vec2::new(self.params.x_scale.forward(center.x), self.params.y_scale.forward(center.y));
+        let min = center + (range.min - center) * factor;
+        let max = center + (range.max - center) * factor;
+        // Stop before the ranges collapse below float resolution
+        if !center.is_finite() || (max - min).cmple(min.abs().max(max.abs()) * 1e-5).any() {
+            return;
+        }
+        self.set_scaled_view(Rect { min, max });
+    }
+
+    /// Shift the shown ranges so the data under the screen point `from` ends up under `to`
+    pub fn pan(&mut self, from: Vec2, to: Vec2) {
+        let range = self.scaled_range();
+        let delta = (self.normalized(from) - self.normalized(to)) * range.size();
+        self.set_scaled_view(Rect { min: range.min + delta, max: range.max + delta });
+    }
+
+    /// Go back to the current ranges following the data
+    pub fn reset_view(&mut self) {
+        self.view = None;
+    }
+
+    /// Visible data point closest on screen to `point`, with its series index
+    pub fn nearest_point(&self, point: Vec2) -> Option<(usize, (f32, f32))> {
+        self.series
+            .iter()
+            .enumerate()
+            .filter(|(_, series)| series.visible)
+            .flat_map(|(i, series)| series.data.iter().map(move |&p| (i, p)))
+            .filter(|&(_, (x, y))| self.to_screen(x, y).is_finite())
+            .min_by(|a, b| {
+                let a = self.to_screen(a.1 .0, a.1 .1).distance_squared(point);
+                let b = self.to_screen(b.1 .0, b.1 .1).distance_squared(point);
+                a.total_cmp(&b)
+            })
+    }
+}
+
+/// Text of a graph, positioned in world coordinates
+#[derive(Clone, Debug)]
+pub struct GraphLabel {
+    pub text: String,
+    pub position: Vec2,
+    pub font_size: f32,
+    pub color: Color,
+    pub anchor: Anchor,
+    /// Counter-clockwise rotation around `position`, in radians
+    pub rotation: f32,
+}
+
+/// Text components of a pooled label
+pub(crate) type LabelQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Text2d,
+        &'static mut TextFont,
+        &'static mut TextColor,
+        &'static mut Transform,
+        &'static mut Anchor,
+        &'static mut RenderLayers,
+    ),
+    Without<GraphWidget>,
+>;
+
+/// `Text2d` children of a widget, updated in place every frame.
+/// Entities are only spawned or despawned when the number of labels changes.
+#[derive(Debug, Default)]
+pub(crate) struct LabelPool {
+    entities: Vec<Entity>,
+}
+
+impl LabelPool {
+    /// Show `labels` under `parent` on `layers`, touching only the components that changed so
+    /// text is laid out again only when it differs
+    pub(crate) fn sync(
+        &mut self,
+        commands: &mut Commands,
+        parent: Entity,
+        labels: &[GraphLabel],
+        layers: &RenderLayers,
+        q_label: &mut LabelQuery,
+    ) {
+        for (i, label) in labels.iter().enumerate() {
+            let Some(&entity) = self.entities.get(i) else {
+                let entity = commands.spawn((
+                    Text2d::new(label.text.clone()),
+                    TextFont {
+                        font_size: label.font_size,
+                        ..default()
+                    },
+                    TextColor(label.color),
+                    Transform::from_translation(label.position.extend(0.2))
+                        .with_rotation(Quat::from_rotation_z(label.rotation)),
+                    label.anchor,
+                    layers.clone(),
+                    ChildOf(parent),
+                )).id();
+                self.entities.push(entity);
+                continue;
+            };
+            // Labels despawned by someone else are left alone
+            let Ok((mut text, mut font, mut color, mut transform, mut anchor, mut label_layers)) = q_label.get_mut(entity) else {
+                continue;
+            };
+            if text.0 != label.text {
+                text.0.clone_from(&label.text);
+            }
+            if font.font_size != label.font_size {
+                font.font_size = label.font_size;
+            }
+            if color.0 != label.color {
+                color.0 = label.color;
+            }
+            let translation = label.position.extend(0.2);
+            let rotation = Quat::from_rotation_z(label.rotation);
+            if transform.translation != translation || transform.rotation != rotation {
+                transform.translation = translation;
+                transform.rotation = rotation;
+            }
+            if *anchor != label.anchor {
+                *anchor = label.anchor;
+            }
+            if *label_layers != *layers {
+                *label_layers = layers.clone();
+            }
+        }
+        if self.entities.len() > labels.len() {
+            for entity in self.entities.drain(labels.len()..) {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// Piece of a series, in world coordinates
+#[derive(Clone, Copy, Debug)]
+pub enum SeriesMark {
+    Segment(Vec2, Vec2),
+    /// Dot of the given radius
+    Dot(Vec2, f32),
+}
+
+/// Gridlines, series and labels of a graph for its current ranges
+#[derive(Clone, Debug, Default)]
+pub struct GraphLayout {
+    /// Gridline segments in world coordinates
+    pub gridlines: Vec<(Vec2, Vec2)>,
+    /// Hover crosshair segments through the point nearest the cursor, drawn in the text color
+    pub crosshair: Vec<(Vec2, Vec2)>,
+    /// Data and legend marks with their color, drawn over the gridlines
+    pub marks: Vec<(SeriesMark, Color)>,
+    pub labels: Vec<GraphLabel>,
+}
+
+impl GraphWidget {
+    /// Gridlines, series and labels for the current ranges, shared by drawing and figure export
+    pub fn layout(&self) -> GraphLayout {
+        let pos = self.params.position;
+        let size = self.params.size;
+        let font_size = self.params.font_size;
+        let range = self.data_range();
+        let mut layout = GraphLayout::default();
+
+        // Horizontal gridlines, labelled on the right side below the line
+        let (y_scale, x_scale) = (self.params.y_scale, self.params.x_scale);
+        let y_spacing = gridline_spacing(&self.params.y_gridlines, range.height());
+        for (y_value, text) in axis_ticks(y_scale, range.min.y, range.max.y, self.params.gridline_origin.y, y_spacing, 0) {
+            let y = self.to_screen(range.min.x, y_value).y;
+            layout.gridlines.push((Vec2::new(pos.x, y), Vec2::new(pos.x + size.x, y)));
+            layout.labels.push(GraphLabel {
+                text,
+                position: Vec2::new(pos.x + size.x, y - 3.0),
+                font_size: font_size * 0.8,
+                color: self.params.grid_color,
+                anchor: Anchor::TOP_RIGHT,
+                rotation: 0.0,
+            });
+        }
+
+        // Vertical gridlines, labelled below the graph. The first aligned line is skipped,
+        // it sits on the left edge where the sliding window enters.
+        let x_spacing = gridline_spacing(&self.params.x_gridlines, range.width());
+        for (x_value, text) in axis_ticks(x_scale, range.min.x, range.max.x, self.params.gridline_origin.x, x_spacing, 1) {
+            let x = self.to_screen(x_value, range.min.y).x;
+            layout.gridlines.push((Vec2::new(x, pos.y), Vec2::new(x, pos.y - size.y)));
+            layout.labels.push(GraphLabel {
+                text,
+                position: Vec2::new(x, pos.y - size.y - 3.0),
+                font_size: font_size * 0.8,
+                color: self.params.grid_color,
+                anchor: Anchor::TOP_CENTER,
+                rotation: 0.0,
+            });
+        }
+
+        // Axis titles, below the x labels and along the left edge reading upwards
+        if !self.params.x_title.is_empty() {
+            layout.labels.push(GraphLabel {
+                text: self.params.x_title.clone(),
+                position: Vec2::new(pos.x + size.x / 2.0, pos.y - size.y - 5.0 - font_size * 0.8),
+                font_size: font_size * 0.9,
+                color: self.params.text_color,
+                anchor: Anchor::TOP_CENTER,
+                rotation: 0.0,
+            });
+        }
+        if !self.params.y_title.is_empty() {
+            layout.labels.push(GraphLabel {
+                text: self.params.y_title.clone(),
+                position: Vec2::new(pos.x - 4.0, pos.y - size.y / 2.0),
+                font_size: font_size * 0.9,
+                color: self.params.text_color,
+                anchor: Anchor::BOTTOM_CENTER,
+                rotation: std::f32::consts::FRAC_PI_2,
+            });
+        }
+
+        // Title (top left), flagging a frozen graph
+        let title = if self.frozen {
+            format!("{} (frozen)", self.params.label)
+        } else {
+            self.params.label.clone()
+        };
+        layout.labels.push(GraphLabel {
+            text: title,
+            position: Vec2::new(pos.x + 5.0, pos.y + 15.0),
+            font_size,
+            color: self.params.text_color,
+            anchor: Anchor::TOP_LEFT,
+            rotation: 0.0,
+        });
+
+        // Zoomed or panned views leave data outside the plot area and log axes leave out values
+        // at or below 0, the lines break there
+        let zoomed = self.view.is_some();
+        for series in self.series.iter().filter(|series| series.visible) {
+            let points: Vec<Vec2> = series.data.iter().map(|&(x, y)| self.to_screen(x, y)).collect();
+            for run in points.split(|&p| !p.is_finite() || (zoomed && !self.contains(p))) {
+                // Lines denser than the pixels keep the extremes of each pixel column
+                if series.style == SeriesStyle::Line && run.len() > 4 * (size.x / DECIMATION_COLUMN) as usize {
+                    let kept: Vec<Vec2> = min_max_columns(run, pos.x, DECIMATION_COLUMN)
+                        .into_iter()
+                        .map(|i| run[i])
+                        .collect();
+                    series_marks(series.style, &kept, series.color, &mut layout.marks);
+                } else {
+                    series_marks(series.style, run, series.color, &mut layout.marks);
+                }
+            }
+        }
+
+        // One digit more than the tick labels
+        let current_text = |(x, y): (f32, f32)| {
+            let (x, y) = (format_readout(x_scale, x, x_spacing), format_readout(y_scale, y, y_spacing));
+            match (self.params.show_current_x, self.params.show_current_y) {
+                (true, true) => format!("({}, {})", x, y),
+                (true, false) => x,
+                (false, true) => y,
+                (false, false) => String::new(),
+            }
+        };
+
+        if self.params.show_legend && self.series.len() > 1 {
+            // One row per series with a swatch of its style, hidden series are dimmed.
+            // Current values go into the rows.
+            let row_height = font_size;
+            let left = pos.x + 6.0;
+            for (i, series) in self.series.iter().enumerate() {
+                let y = pos.y - 6.0 - row_height * (i as f32 + 0.5);
+                let (color, text_color) = if series.visible {
+                    (series.color, self.params.text_color)
+                } else {
+                    (series.color.with_alpha(0.25), self.params.grid_color)
+                };
+                let swatch = match series.style {
+                    SeriesStyle::Points { .. } => vec![Vec2::new(left + 8.0, y)],
+                    _ => vec![Vec2::new(left, y), Vec2::new(left + 16.0, y)],
+                };
+                series_marks(series.style, &swatch, color, &mut layout.marks);
+
+                let mut text = series.name.clone();
+                if let Some(&point) = series.data.back() {
+                    let value = current_text(point);
+                    if !value.is_empty() {
+                        text = format!("{}  {}", text, value);
+                    }
+                }
+                layout.labels.push(GraphLabel {
+                    text,
+                    position: Vec2::new(left + 20.0, y),
+                    font_size: font_size * 0.8,
+                    color: text_color,
+                    anchor: Anchor::CENTER_LEFT,
+                    rotation: 0.0,
+                });
+            }
+        } else if let Some(&point) = self.series.first().and_then(|series| series.data.back()) {
+            // Current values (top right)
+            let current_text = current_text(point);
+            if !current_text.is_empty() {
+                layout.labels.push(GraphLabel {
+                    text: current_text,
+                    position: Vec2::new(pos.x + size.x - 5.0, pos.y + 15.0),
+                    font_size,
+                    color: self.params.text_color,
+                    anchor: Anchor::TOP_RIGHT,
+                    rotation: 0.0,
+                });
+            }
+        }
+
+        // Hover crosshair and readout of the data point nearest the cursor
+        if let Some((index, (x, y))) = self.cursor.filter(|_| !self.exporting).and_then(|cursor| self.nearest_point(cursor)) {
+            let point = self.to_screen(x, y);
+            if self.contains(point) {
+                layout.crosshair.push((Vec2::new(pos.x, point.y), Vec2::new(pos.x + size.x, point.y)));
+                layout.crosshair.push((Vec2::new(point.x, pos.y), Vec2::new(point.x, pos.y - size.y)));
+                layout.marks.push((SeriesMark::Dot(point, 3.0), self.series[index].color));
+                // Readout on the side of the point facing the center of the plot
+                let (offset, anchor) = if point.x > pos.x + size.x / 2.0 {
+                    (Vec2::new(-6.0, 4.0), Anchor::BOTTOM_RIGHT)
+                } else {
+                    (Vec2::new(6.0, 4.0), Anchor::BOTTOM_LEFT)
+                };
+                layout.labels.push(GraphLabel {
+                    text: format!("({}, {})", format_readout(x_scale, x, x_spacing), format_readout(y_scale, y, y_spacing)),
+                    position: point + offset,
+                    font_size: font_size * 0.8,
+                    color: self.params.text_color,
+                    anchor,
+                    rotation: 0.0,
+                });
+            }
+        }
+
+        layout
+    }
+
+    /// Write the points of every series as CSV, one `series,x,y` line per point.
+    /// The x and y columns are named after the axis titles when they are set.
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let column = |title: &str, default: &str| csv_field(if title.is_empty() { default } else { title });
+        writeln!(
+            writer,
+            "series,{},{}",
+            column(&self.params.x_title, "x"),
+            column(&self.params.y_title, "y")
+        )?;
+        for series in &self.series {
+            let name = csv_field(&series.name);
+            for &(x, y) in &series.data {
+                writeln!(writer, "{},{},{}", name, x, y)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mesh assets of graphs drawn with `GraphBackend::Mesh`
+#[derive(SystemParam)]
+pub struct GraphMeshes<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+}
+
+/// Child entity of a widget holding its series as one mesh, spawned when first needed
+#[derive(Debug, Default)]
+pub(crate) struct SeriesMesh {
+    entity: Option<(Entity, Handle<Mesh>, RenderLayers)>,
+}
+
+impl SeriesMesh {
+    /// Show `marks` under `parent` on `layers`, the entity is despawned when there are none
+    pub(crate) fn sync(
+        &mut self,
+        commands: &mut Commands,
+        meshes: &mut GraphMeshes,
+        parent: Entity,
+        marks: &[(SeriesMark, Color)],
+        layers: &RenderLayers,
+    ) {
+        if marks.is_empty() {
+            if let Some((entity, ..)) = self.entity.take() {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+        let (entity, handle, mesh_layers) = self.entity.get_or_insert_with(|| {
+            let handle = meshes.meshes.add(Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+            ));
+            // White, so the vertex colors come through as is
+            let material = meshes.materials.add(ColorMaterial::from_color(Color::WHITE));
+            let entity = commands.spawn((
+                Mesh2d(handle.clone()),
+                MeshMaterial2d(material),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                // The mesh bounds change every frame
+                NoFrustumCulling,
+                layers.clone(),
+                ChildOf(parent),
+                Name::new("GraphSeriesMesh"),
+            )).id();
+            (entity, handle, layers.clone())
+        });
+        if *mesh_layers != *layers {
+            *mesh_layers = layers.clone();
+            commands.entity(*entity).insert(layers.clone());
+        }
+        if let Some(mesh) = meshes.meshes.get_mut(handle) {
+            write_marks_mesh(mesh, marks);
+        }
+    }
+}
+
+/// Sides of the polygon standing in for a dot
+const DOT_SIDES: usize = 8;
+
+/// Replaces the geometry of `mesh` by `marks`: segments become quads of the line thickness,
+/// extended by half of it at both ends so consecutive segments join, and dots become polygons
+fn write_marks_mesh(mesh: &mut Mesh, marks: &[(SeriesMark, Color)]) {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for &(mark, color) in marks {
+        let color = color.to_linear().to_f32_array();
+        let first = positions.len() as u32;
+        match mark {
+            SeriesMark::Segment(from, to) => {
+                let direction = (to - from).normalize_or(Vec2::X) * LINE_THICKNESS / 2.0;
+                let side = direction.perp();
+                let (from, to) = (from - direction, to + direction);
+                for corner in [from - side, from + side, to + side, to - side] {
+                    positions.push(corner.extend(0.0).to_array());
+                }
+                indices.extend([0, 1, 2, 0, 2, 3].map(|i| first + i));
+            }
+            SeriesMark::Dot(center, radius) => {
+                positions.push(center.extend(0.0).to_array());
+                for i in 0..DOT_SIDES {
+                    let angle = i as f32 * std::f32::consts::TAU / DOT_SIDES as f32;
+                    positions.push((center + Vec2::from_angle(angle) * radius).extend(0.0).to_array());
+                }
+                for i in 0..DOT_SIDES as u32 {
+                    indices.extend([first, first + 1 + i, first + 1 + (i + 1) % DOT_SIDES as u32]);
+                }
+            }
+        }
+        colors.resize(positions.len(), color);
+    }
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
 }
 
 /// System to draw the graph widget
 pub fn draw_graph_widget(
     mut commands: Commands,
     mut painter: ShapePainter,
+    mut meshes: GraphMeshes,
     mut query: Query<(Entity, &mut GraphWidget)>,
+    mut q_label: LabelQuery,
 ) {
     for (entity, mut graph) in query.iter_mut() {
-        // Clean up old text entities
-        for text_entity in graph.text_entities.drain(..) {
-            commands.entity(text_entity).despawn();
-        }
-        
-        draw_single_graph(&mut commands, &mut painter, &mut graph, entity);
+        let layout = draw_single_graph(&mut painter, &graph);
+        let layers = graph.render_layers();
+        let mesh_marks = mesh_marks(&graph, &layout);
+        graph.series_mesh.sync(&mut commands, &mut meshes, entity, mesh_marks, &layers);
+        graph.labels.sync(&mut commands, entity, &layout.labels, &layers, &mut q_label);
     }
 }
 
-fn draw_single_graph(
-    commands: &mut Commands,
+/// Marks `graph` leaves to its series mesh, none unless it uses `GraphBackend::Mesh`
+pub(crate) fn mesh_marks<'a>(graph: &GraphWidget, layout: &'a GraphLayout) -> &'a [(SeriesMark, Color)] {
+    match graph.params.backend {
+        GraphBackend::Painter => &[],
+        GraphBackend::Mesh => &layout.marks,
+    }
+}
+
+/// Draws the gridlines of `graph`, and its series unless they go into a mesh.
+/// Returns the layout for the labels and series mesh to show with them.
+pub(crate) fn draw_single_graph(
     painter: &mut ShapePainter,
-    graph: &mut GraphWidget,
-    _parent_entity: Entity,
-) {
-    let pos = graph.params.position;
-    let size = graph.params.size;
+    graph: &GraphWidget,
+) -> GraphLayout {
+    let layout = graph.layout();
 
+    painter.render_layers = (graph.params.placement.is_some() || graph.exporting).then(|| graph.render_layers());
     painter.set_color(graph.params.grid_color);
-    painter.thickness = 0.25;
+    painter.thickness = GRID_THICKNESS;
+    for &(from, to) in &layout.gridlines {
+        painter.line(from.extend(0.0), to.extend(0.0));
+    }
+    painter.set_color(graph.params.text_color);
+    for &(from, to) in &layout.crosshair {
+        painter.line(from.extend(0.05), to.extend(0.05));
+    }
 
-    // Draw horizontal gridlines with labels
-    let y_range = graph.y_max - graph.y_min;
-    let y_spacing = match &graph.params.y_gridlines {
-        GridlineConfig::Fixed { spacing } => *spacing,
-        GridlineConfig::Dynamic { min_spacing, num_lines } => {
-            let target_spacing = y_range / *num_lines as f32;
-            let multiplier = (target_spacing / min_spacing).ceil().max(1.0);
-            min_spacing * multiplier
-        }
+    // Draw the series and legend swatches
+    let base = painter.transform;
+    painter.thickness = LINE_THICKNESS;
+    painter.hollow = false;
+    let painted: &[(SeriesMark, Color)] = match graph.params.backend {
+        GraphBackend::Painter => &layout.marks,
+        GraphBackend::Mesh => &[],
     };
+    for &(mark, color) in painted {
+        painter.set_color(color);
+        match mark {
+            SeriesMark::Segment(from, to) => {
+                painter.line(from.extend(0.1), to.extend(0.1));
+            }
+            SeriesMark::Dot(center, radius) => {
+                painter.transform = base;
+                painter.translate(center.extend(0.1));
+                painter.circle(radius);
+                painter.transform = base;
+            }
+        }
+    }
+
+    layout
+}
+
+/// Marks drawing the polyline `points` in `style` and `color`
+fn series_marks(style: SeriesStyle, points: &[Vec2], color: Color, marks: &mut Vec<(SeriesMark, Color)>) {
+    match style {
+        SeriesStyle::Line => {
+            marks.extend(points.windows(2).map(|pair| (SeriesMark::Segment(pair[0], pair[1]), color)));
+        }
+        SeriesStyle::Dashed { dash, gap } => {
+            let dash = dash.max(0.5);
+            let period = dash + gap.max(0.0);
+            // Distance into the current dash period, carried across points
+            let mut phase = 0.0;
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                let length = a.distance(b);
+                if length <= f32::EPSILON {
+                    continue;
+                }
+                let direction = (b - a) / length;
+                let mut s = 0.0;
+                while s < length {
+                    let drawing = phase < dash;
+                    let step = (if drawing { dash - phase } else { period - phase }).min(length - s);
+                    if drawing {
+                        marks.push((SeriesMark::Segment(a + direction * s, a + direction * (s + step)), color));
+                    }
+                    s += step;
+                    phase = (phase + step) % period;
+                }
+            }
+        }
+        SeriesStyle::Points { radius } => {
+            marks.extend(points.iter().map(|&p| (SeriesMark::Dot(p, radius), color)));
+        }
+    }
+}
 
-    let y_origin = graph.params.gridline_origin.y;
-    let first_y_aligned = y_origin + ((graph.y_min - y_origin) / y_spacing).floor() * y_spacing;
-    
-    let mut y_value = first_y_aligned;
-    let mut y_labels = Vec::new();
-    while y_value <= graph.y_max {
-        if y_value >= graph.y_min {
-            let screen_pos = graph.to_screen(graph.x_min, y_value);
-            painter.line(
-                Vec3::new(pos.x, screen_pos.y, 0.0),
-                Vec3::new(pos.x + size.x, screen_pos.y, 0.0),
-            );
-            y_labels.push((y_value, screen_pos.y));
-        }
-        y_value += y_spacing;
-    }
-
-    // Draw vertical gridlines with labels
-    let x_range = graph.x_max - graph.x_min;
-    let x_spacing = match &graph.params.x_gridlines {
+/// CSV field holding `text`, quoted when it contains a separator, quote or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Spacing between gridlines covering `range`
+fn gridline_spacing(config: &GridlineConfig, range: f32) -> f32 {
+    match config {
         GridlineConfig::Fixed { spacing } => *spacing,
         GridlineConfig::Dynamic { min_spacing, num_lines } => {
-            let target_spacing = x_range / *num_lines as f32;
-            let multiplier = (target_spacing / min_spacing).ceil().max(1.0);
-            min_spacing * multiplier
+            // Heckbert's labeling: a nice range split into nice steps
+            let spacing = nice_number(nice_number(range, false) / (*num_lines).max(1) as f32, true);
+            spacing.max(nice_number(*min_spacing, false))
         }
-    };
+    }
+}
 
-    let x_origin = graph.params.gridline_origin.x;
-    let first_x_aligned = (x_origin + x_spacing) + ((graph.x_min - x_origin) / x_spacing).floor() * x_spacing;
-    
-    let mut x_value = first_x_aligned;
-    let mut x_labels = Vec::new();
-    while x_value <= graph.x_max {
-        if x_value >= graph.x_min {
-            let screen_pos = graph.to_screen(x_value, graph.y_min);
-            painter.line(
-                Vec3::new(screen_pos.x, pos.y, 0.0),
-                Vec3::new(screen_pos.x, pos.y - size.y, 0.0),
-            );
-            x_labels.push((x_value, screen_pos.x));
-        }
-        x_value += x_spacing;
-    }
-
-    // Draw the data line
-    if graph.data.len() >= 2 {
-        painter.set_color(graph.params.line_color);
-        painter.thickness = 2.0;
-        
-        for i in 0..graph.data.len() - 1 {
-            let (x1, y1) = graph.data[i];
-            let (x2, y2) = graph.data[i + 1];
-            
-            let p1 = graph.to_screen(x1, y1);
-            let p2 = graph.to_screen(x2, y2);
-            
-            painter.line(
-                Vec3::new(p1.x, p1.y, 0.1),
-                Vec3::new(p2.x, p2.y, 0.1),
-            );
-        }
-    }
-
-    // Spawn text labels
-    let font_size = graph.params.font_size;
-    let text_color = graph.params.text_color;
-    
-    // Title (top left)
-    let title_entity = commands.spawn((
-        Text2d::new(&graph.params.label),
-        TextFont {
-            font_size,
-            ..default()
-        },
-        TextColor(text_color),
-        Transform::from_translation(Vec3::new(pos.x + 5.0, pos.y + 15.0, 0.2)),
-        Anchor::TOP_LEFT,
-    )).id();
-    graph.text_entities.push(title_entity);
-
-    // Current values (top right)
-    if !graph.data.is_empty() {
-        let (current_x, current_y) = graph.data.back().copied().unwrap();
-        let mut current_text = String::new();
-        
-        if graph.params.show_current_x && graph.params.show_current_y {
-            current_text = format!("({:.2}, {:.2})", current_x, current_y);
-        } else if graph.params.show_current_x {
-            current_text = format!("{:.2}", current_x);
-        } else if graph.params.show_current_y {
-            current_text = format!("{:.2}", current_y);
-        }
-        
-        if !current_text.is_empty() {
-            let current_entity = commands.spawn((
-                Text2d::new(current_text),
-                TextFont {
-                    font_size,
-                    ..default()
-                },
-                TextColor(text_color),
-                Transform::from_translation(Vec3::new(pos.x + size.x - 5.0, pos.y + 15.0, 0.2)),
-                Anchor::TOP_RIGHT,
-            )).id();
-            graph.text_entities.push(current_entity);
+/// Multiples of `spacing` (offset by `origin`) within [min, max], skipping the first `skip`
+fn gridline_values(min: f32, max: f32, origin: f32, spacing: f32, skip: usize) -> impl Iterator<Item = f32> {
+    let valid = spacing.is_finite() && spacing > 0.0 && (max - min) / spacing <= MAX_GRIDLINES as f32;
+    let first = origin + ((min - origin) / spacing).floor() * spacing;
+    (skip..MAX_GRIDLINES + 2)
+        .take_while(move |_| valid)
+        .map(move |i| first + i as f32 * spacing)
+        .take_while(move |&value| value <= max + spacing * 1e-4)
+        .filter(move |&value| value >= min - spacing * 1e-4)
+}
+
+/// Gridline values within [min, max] with their labels. Linear axes have one every `spacing`,
+/// skipping the first `skip`; log axes have one per decade, with 2 and 5 in between when
+/// only a few decades are shown.
+fn axis_ticks(scale: AxisScale, min: f32, max: f32, origin: f32, spacing: f32, skip: usize) -> Vec<(f32, String)> {
+    match scale {
+        AxisScale::Linear => gridline_values(min, max, origin, spacing, skip)
+            .map(|value| (value, format_tick(value, spacing)))
+            .collect(),
+        AxisScale::Log => {
+            if !(min > 0.0 && max > min) {
+                return Vec::new();
+            }
+            let (first, last) = (min.log10().floor() as i32, max.log10().ceil() as i32);
+            let decades = (last - first).max(1) as usize;
+            let mantissas: &[f32] = if decades <= 2 { &[1.0, 2.0, 5.0] } else { &[1.0] };
+            // Every n-th decade when there are many
+            let step = decades.div_ceil(8);
+            let mut ticks = Vec::new();
+            for exponent in (first..=last).step_by(step) {
+                let decade = 10f32.powi(exponent);
+                for &mantissa in mantissas {
+                    let value = mantissa * decade;
+                    if (min..=max).contains(&value) {
+                        ticks.push((value, format_tick(value, decade)));
+                    }
+                }
+            }
+            ticks
         }
     }
+}
 
-    // Y-axis labels (right side, below gridline, right-aligned to graph edge)
-    let right_x = pos.x + size.x;
-    for (value, y_pos) in y_labels {
-        let label_entity = commands.spawn((
-            Text2d::new(format!("{:.1}", value)),
-            TextFont {
-                font_size: font_size * 0.8,
-                ..default()
-            },
-            TextColor(graph.params.grid_color),
-            Transform::from_translation(Vec3::new(right_x, y_pos - 3.0, 0.2)),
-            Anchor::TOP_RIGHT,
-        )).id();
-        graph.text_entities.push(label_entity);
-    }
-
-    // X-axis labels (bottom, aligned to gridlines)
-    let bottom_y = pos.y - size.y;
-    for (value, x_pos) in x_labels {
-        let label_entity = commands.spawn((
-            Text2d::new(format!("{:.1}", value)),
-            TextFont {
-                font_size: font_size * 0.8,
-                ..default()
-            },
-            TextColor(graph.params.grid_color),
-            Transform::from_translation(Vec3::new(x_pos, bottom_y - 3.0, 0.2)),
-            Anchor::TOP_CENTER,
-        )).id();
-        graph.text_entities.push(label_entity);
+/// Value readout with one digit more than the tick labels, or three significant digits on a log axis
+fn format_readout(scale: AxisScale, value: f32, spacing: f32) -> String {
+    match scale {
+        AxisScale::Linear => format_tick(value, spacing / 10.0),
+        AxisScale::Log => format_tick(value, 10f32.powf(value.abs().log10().floor() - 2.0)),
+    }
+}
+
+/// 1, 2 or 5 × 10^k close to `x`: the nearest one when `round`, else the smallest one not below `x`
+pub fn nice_number(x: f32, round: bool) -> f32 {
+    if !x.is_finite() || x <= 0.0 {
+        return 1.0;
+    }
+    let power = 10f32.powf(x.log10().floor());
+    // Rounding in the power would push exact fractions like 2 past their bracket
+    let fraction = x / power * (1.0 - 1e-5);
+    let nice = if round {
+        match fraction {
+            f if f < 1.5 => 1.0,
+            f if f < 3.0 => 2.0,
+            f if f < 7.0 => 5.0,
+            _ => 10.0,
+        }
+    } else {
+        match fraction {
+            f if f <= 1.0 => 1.0,
+            f if f <= 2.0 => 2.0,
+            f if f <= 5.0 => 5.0,
+            _ => 10.0,
+        }
+    };
+    nice * power
+}
+
+/// Label of `value` on an axis with ticks every `spacing`: just enough decimals to tell the
+/// ticks apart, in scientific notation for very large or small magnitudes
+pub fn format_tick(value: f32, spacing: f32) -> String {
+    let spacing = if spacing.is_finite() && spacing > 0.0 { spacing } else { 1.0 };
+    // Accumulated rounding would otherwise print as -0 or 1e-9
+    let value = if value.abs() < spacing * 1e-3 { 0.0 } else { value };
+    let step_exponent = spacing.log10().floor();
+    let magnitude = value.abs().max(spacing);
+    if !(1e-3..1e5).contains(&magnitude) {
+        let decimals = (magnitude.log10().floor() - step_exponent).clamp(0.0, 6.0) as usize;
+        format!("{:.*e}", decimals, value)
+    } else {
+        let decimals = (-step_exponent).max(0.0) as usize;
+        format!("{:.*}", decimals, value)
     }
 }
 
@@ -370,3 +1268,205 @@ pub fn spawn_graph_widget(
         Name::new("GraphWidget"),
     )).id()
 }
+
+/// Spawn a graph widget entity plotting several series on shared axes
+pub fn spawn_multi_series_graph_widget(
+    commands: &mut Commands,
+    params: GraphParams,
+    series: Vec<GraphSeries>,
+) -> Entity {
+    commands.spawn((
+        GraphWidget::with_series(params, series),
+        Name::new("GraphWidget"),
+    )).id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bounds of the points of series `index`, scanned
+    fn scanned_bounds(graph: &GraphWidget, index: usize) -> Rect {
+        let mut points = graph.series[index].data.iter().map(|&(x, y)| Vec2::new(x, y));
+        let first = points.next().unwrap();
+        points.fold(Rect::from_corners(first, first), |bounds, point| bounds.union_point(point))
+    }
+
+    #[test]
+    fn running_bounds_follow_evictions() {
+        let mut graph = GraphWidget::new(GraphParams { max_points: 50, ..default() });
+        for i in 0..2000 {
+            let t = i as f32 * 0.05;
+            let spike = if i % 97 == 0 { 5.0 } else { 0.0 };
+            graph.add_point(t, (0.7 * t).sin() * (1.0 + (0.03 * t).cos()) + spike);
+            let bounds = graph.bounds[0].rect().unwrap();
+            assert_eq!(bounds, scanned_bounds(&graph, 0), "after point {}", i);
+        }
+        // Every point went through the running bounds, none was rescanned
+        assert_eq!(graph.bounds[0].next, 2000);
+        // Increasing times leave a single maximum candidate
+        assert_eq!(graph.bounds[0].max_x.len(), 1);
+    }
+
+    #[test]
+    fn running_bounds_follow_phase_plots() {
+        let mut graph = GraphWidget::new(GraphParams { max_points: 64, ..default() });
+        for i in 0..1000 {
+            let t = i as f32 * 0.1;
+            graph.add_point(t.cos() * (1.0 + 0.01 * t), (2.0 * t).sin());
+            assert_eq!(graph.bounds[0].rect().unwrap(), scanned_bounds(&graph, 0), "after point {}", i);
+        }
+        assert_eq!(graph.bounds[0].next, 1000);
+    }
+
+    #[test]
+    fn bounds_rescan_on_scale_change() {
+        let mut graph = GraphWidget::new(GraphParams { max_points: 10, ..default() });
+        for i in 1..=20 {
+            graph.add_point(i as f32, i as f32 * 10.0);
+        }
+        graph.params.y_scale = AxisScale::Log;
+        graph.add_point(21.0, 1000.0);
+        let bounds = graph.bounds[0].rect().unwrap();
+        assert!((bounds.min.y - 120.0f32.log10()).abs() < 1e-6 && (bounds.max.y - 3.0).abs() < 1e-6, "{:?}", bounds);
+    }
+
+    /// Tick values and labels of a linear axis over [min, max] with about four dynamic gridlines
+    fn linear_ticks(min: f32, max: f32) -> (f32, Vec<(f32, String)>) {
+        let config = GridlineConfig::Dynamic { min_spacing: 1e-9, num_lines: 4 };
+        let spacing = gridline_spacing(&config, max - min);
+        (spacing, axis_ticks(AxisScale::Linear, min, max, 0.0, spacing, 0))
+    }
+
+    #[test]
+    fn nice_numbers() {
+        assert_eq!(nice_number(1.0, false), 1.0);
+        assert_eq!(nice_number(15.9, false), 20.0);
+        assert_eq!(nice_number(0.25, true), 0.2);
+        assert_eq!(nice_number(4.0, true), 5.0);
+        assert_eq!(nice_number(7.5, true), 10.0);
+        assert_eq!(nice_number(0.0, true), 1.0);
+        assert_eq!(nice_number(f32::NAN, false), 1.0);
+    }
+
+    #[test]
+    fn unit_range_ticks() {
+        let (spacing, ticks) = linear_ticks(0.0, 1.0);
+        assert!((spacing - 0.2).abs() < 1e-6);
+        let labels: Vec<&str> = ticks.iter().map(|(_, label)| label.as_str()).collect();
+        assert_eq!(labels, ["0.0", "0.2", "0.4", "0.6", "0.8", "1.0"]);
+    }
+
+    #[test]
+    fn mixed_sign_range_ticks() {
+        let (spacing, ticks) = linear_ticks(-3.7, 12.2);
+        assert_eq!(spacing, 5.0);
+        let labels: Vec<&str> = ticks.iter().map(|(_, label)| label.as_str()).collect();
+        assert_eq!(labels, ["0", "5", "10"]);
+    }
+
+    #[test]
+    fn tiny_range_ticks() {
+        let (spacing, ticks) = linear_ticks(1e-6, 3e-6);
+        assert!((spacing - 5e-7).abs() < 1e-12, "spacing {}", spacing);
+        assert_eq!(ticks.len(), 5);
+        for pair in ticks.windows(2) {
+            assert!((pair[1].0 - pair[0].0 - spacing).abs() < 1e-12);
+            assert_ne!(pair[0].1, pair[1].1, "ticks print alike");
+        }
+        assert!(ticks.iter().all(|(_, label)| label.contains('e')), "{:?}", ticks);
+    }
+
+    #[test]
+    fn degenerate_range_ticks() {
+        let (spacing, ticks) = linear_ticks(2.0, 2.0);
+        assert!(spacing.is_finite() && spacing > 0.0);
+        assert_eq!(ticks.len(), 1);
+        assert!((ticks[0].0 - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tick_labels() {
+        assert_eq!(format_tick(0.5, 0.1), "0.5");
+        assert_eq!(format_tick(-1e-9, 0.1), "0.0");
+        assert_eq!(format_tick(120000.0, 10000.0), "1.2e5");
+        assert_eq!(format_tick(3.0, f32::NAN), "3");
+    }
+
+
+    fn assert_range(range: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (range.0 - expected.0).abs() < 1e-5 && (range.1 - expected.1).abs() < 1e-5,
+            "{:?} != {:?}",
+            range,
+            expected
+        );
+    }
+
+    #[test]
+    fn data_and_fixed_ranges() {
+        let linear = AxisScale::Linear;
+        assert_range(RangeMode::Data.apply(linear, (0.0, 1.0), (2.0, 3.0), 0.1), (2.0, 3.0));
+        assert_range(RangeMode::Fixed { min: -1.0, max: 1.0 }.apply(linear, (0.0, 5.0), (2.0, 3.0), 0.1), (-1.0, 1.0));
+        // In decades on a log axis
+        assert_range(RangeMode::Fixed { min: 1.0, max: 1000.0 }.apply(AxisScale::Log, (0.0, 1.0), (0.0, 1.0), 0.1), (0.0, 3.0));
+    }
+
+    #[test]
+    fn sliding_window_range() {
+        let window = RangeMode::SlidingWindow { span: 5.0 };
+        assert_range(window.apply(AxisScale::Linear, (0.0, 10.0), (0.0, 20.0), 0.1), (15.0, 20.0));
+        // 100 - 50 = 50, in decades
+        assert_range(window_log(50.0, (0.0, 2.0)), (50f32.log10(), 2.0));
+        // A window reaching below 0 on a log axis keeps the data start
+        assert_range(window_log(200.0, (0.5, 2.0)), (0.5, 2.0));
+    }
+
+    fn window_log(span: f32, data: (f32, f32)) -> (f32, f32) {
+        RangeMode::SlidingWindow { span }.apply(AxisScale::Log, (0.0, 1.0), data, 0.1)
+    }
+
+    #[test]
+    fn expand_range_grows_and_never_shrinks() {
+        let linear = AxisScale::Linear;
+        // Within 10% of the top edge grows it by the threshold distance
+        assert_range(RangeMode::Expand.apply(linear, (0.0, 10.0), (1.0, 9.5), 0.1), (0.0, 10.5));
+        assert_range(RangeMode::Expand.apply(linear, (0.0, 10.0), (-2.0, 5.0), 0.1), (-3.0, 10.0));
+        assert_range(RangeMode::Expand.apply(linear, (0.0, 10.0), (4.0, 5.0), 0.1), (0.0, 10.0));
+        // No current range yet
+        assert_range(RangeMode::Expand.apply(linear, (f32::NAN, 1.0), (4.0, 5.0), 0.1), (4.0, 5.0));
+    }
+
+    #[test]
+    fn autoscale_range_shrinks_below_threshold() {
+        let autoscale = RangeMode::Autoscale { shrink_below: 0.25 };
+        // Data spanning 70% of the range keeps it
+        assert_range(autoscale.apply(AxisScale::Linear, (0.0, 10.0), (2.0, 9.0), 0.1), (0.0, 10.0));
+        // Data spanning 10% refits with the threshold margin
+        assert_range(autoscale.apply(AxisScale::Linear, (0.0, 10.0), (4.0, 5.0), 0.1), (3.9, 5.1));
+        // Growing still works
+        assert_range(autoscale.apply(AxisScale::Linear, (0.0, 10.0), (0.0, 12.0), 0.1), (-1.0, 13.0));
+    }
+
+    #[test]
+    fn log_axis_leaves_out_non_positive_data() {
+        let mut graph = GraphWidget::new(GraphParams {
+            y_scale: AxisScale::Log,
+            y_range: RangeMode::Data,
+            ..default()
+        });
+        for (x, y) in [(0.0, -1.0), (1.0, 0.0), (2.0, 10.0), (3.0, 100.0)] {
+            graph.add_point(x, y);
+        }
+        assert!((graph.y_min - 10.0).abs() < 1e-3 && (graph.y_max - 100.0).abs() < 1e-2, "{} {}", graph.y_min, graph.y_max);
+        // Points off the log axis are left out whole
+        assert_eq!((graph.x_min, graph.x_max), (2.0, 3.0));
+
+        // Only non-positive values leave the ranges as they were
+        let mut graph = GraphWidget::new(GraphParams { y_scale: AxisScale::Log, ..default() });
+        let before = (graph.y_min, graph.y_max);
+        graph.add_point(0.0, -5.0);
+        assert_eq!((graph.y_min, graph.y_max), before);
+    }
+
+}
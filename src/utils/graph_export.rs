@@ -0,0 +1,235 @@
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::RenderTarget;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::view::screenshot::{save_to_disk, Screenshot};
+
+#[cfg(target_arch = "wasm32")]
+use crate::utils::figure::download_bytes;
+use crate::utils::graph::GraphWidget;
+use crate::utils::histogram::HistogramWidget;
+use crate::utils::spectrum::SpectrumWidget;
+
+/// Frames the offscreen camera renders before its image is read back
+const CAPTURE_DELAY: u32 = 2;
+
+/// Render layer of the offscreen cameras, the captured graph is drawn on it as well
+pub const GRAPH_EXPORT_LAYER: usize = 8;
+
+/// Graphs, spectrum plots and histograms, with the entity holding them
+type ExportGraphQuery<'w, 's> =
+    Query<'w, 's, (Entity, AnyOf<(&'static GraphWidget, &'static SpectrumWidget, &'static HistogramWidget)>)>;
+
+/// Mutable `ExportGraphQuery`, to mark the captured graphs
+type ExportGraphMutQuery<'w, 's> =
+    Query<'w, 's, (Entity, AnyOf<(&'static mut GraphWidget, &'static mut SpectrumWidget, &'static mut HistogramWidget)>)>;
+
+/// Plot of whichever widget the entity holds
+fn widget_graph<'a>(
+    (graph, spectrum, histogram): (Option<&'a GraphWidget>, Option<&'a SpectrumWidget>, Option<&'a HistogramWidget>),
+) -> Option<&'a GraphWidget> {
+    graph
+        .or(spectrum.map(|spectrum| &spectrum.graph))
+        .or(histogram.map(|histogram| &histogram.graph))
+}
+
+/// Widget held by an entity of `ExportGraphMutQuery`
+type WidgetMut<'a> = (Option<Mut<'a, GraphWidget>>, Option<Mut<'a, SpectrumWidget>>, Option<Mut<'a, HistogramWidget>>);
+
+/// Mutable plot of whichever widget the entity holds
+fn widget_graph_mut((graph, spectrum, histogram): WidgetMut<'_>) -> Option<&mut GraphWidget> {
+    graph
+        .map(Mut::into_inner)
+        .or(spectrum.map(|spectrum| &mut spectrum.into_inner().graph))
+        .or(histogram.map(|histogram| &mut histogram.into_inner().graph))
+}
+
+/// Image of a graph being rendered by its own camera
+struct GraphCapture {
+    /// Widget drawn on the export layer until the readback
+    widget: Entity,
+    camera: Entity,
+    image: Handle<Image>,
+    path: String,
+    /// Frames left before the readback, the camera goes once it is requested
+    frames: u32,
+}
+
+/// Requested exports of graph data (CSV) and images (PNG), see `GraphExportPlugin`.
+/// Files are numbered `graph-001-<title>.csv`, `graph-001-<title>.png`... in the working
+/// directory, on the web they are downloaded by the browser.
+#[derive(Resource, Default)]
+pub struct GraphExport {
+    /// Widgets to export, `None` exporting all of them
+    requests: Vec<Option<Entity>>,
+    captures: Vec<GraphCapture>,
+    count: usize,
+}
+
+impl GraphExport {
+    /// Export the widget on `entity` (a graph, spectrum or histogram) at the end of the frame
+    pub fn request(&mut self, entity: Entity) {
+        self.requests.push(Some(entity));
+    }
+
+    /// Export every widget at the end of the frame
+    pub fn request_all(&mut self) {
+        self.requests.push(None);
+    }
+}
+
+/// System requesting an export on F10: the graph under the cursor, or all of them
+pub fn request_graph_export(keys: Res<ButtonInput<KeyCode>>, mut export: ResMut<GraphExport>, q_graph: ExportGraphQuery) {
+    if !keys.just_pressed(KeyCode::F10) {
+        return;
+    }
+    let hovered = q_graph
+        .iter()
+        .find(|&(_, widget)| widget_graph(widget).is_some_and(|graph| graph.cursor.is_some()));
+    match hovered {
+        Some((entity, _)) => export.request(entity),
+        None => export.request_all(),
+    }
+}
+
+/// System writing the requested CSV files and rendering the requested graphs offscreen.
+/// Each graph gets a camera framing its plot area and labels on `GRAPH_EXPORT_LAYER`,
+/// which only the graph being captured is drawn on, so the scene and overlapping graphs stay out of the image.
+pub fn export_graphs(
+    mut commands: Commands,
+    mut export: ResMut<GraphExport>,
+    mut images: ResMut<Assets<Image>>,
+    clear_color: Res<ClearColor>,
+    mut q_graph: ExportGraphMutQuery,
+) {
+    let export = &mut *export;
+
+    // Read back the images rendered for long enough, then drop their cameras
+    let mut captured = Vec::new();
+    export.captures.retain_mut(|capture| {
+        if capture.frames > 0 {
+            capture.frames -= 1;
+            return true;
+        }
+        commands
+            .spawn(Screenshot::image(capture.image.clone()))
+            .observe(save_to_disk(capture.path.clone()));
+        commands.entity(capture.camera).despawn();
+        captured.push(capture.widget);
+        false
+    });
+    for entity in captured {
+        if !export.captures.iter().any(|capture| capture.widget == entity)
+            && let Ok((_, widget)) = q_graph.get_mut(entity)
+            && let Some(graph) = widget_graph_mut(widget)
+        {
+            graph.exporting = false;
+        }
+    }
+
+    let requests = std::mem::take(&mut export.requests);
+    if requests.is_empty() {
+        return;
+    }
+    let all = requests.contains(&None);
+    for (entity, widget) in &mut q_graph {
+        if !all && !requests.contains(&Some(entity)) {
+            continue;
+        }
+        let Some(graph) = widget_graph_mut(widget) else {
+            continue;
+        };
+
+        export.count += 1;
+        let name = format!("graph-{:03}-{}", export.count, file_stem(&graph.params.label));
+        write_csv(graph, &format!("{name}.csv"));
+
+        let rect = export_rect(graph);
+        let size = rect.size().ceil().max(Vec2::ONE).as_uvec2();
+        let image = images.add(Image::new_target_texture(size.x, size.y, TextureFormat::bevy_default()));
+        let camera = commands.spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(image.clone().into()),
+                clear_color: ClearColorConfig::Custom(clear_color.0),
+                order: -1,
+                ..default()
+            },
+            Tonemapping::None,
+            Transform::from_translation(rect.center().extend(0.0)),
+            RenderLayers::layer(GRAPH_EXPORT_LAYER),
+            Name::new("GraphExportCamera"),
+        )).id();
+        graph.exporting = true;
+        export.captures.push(GraphCapture {
+            widget: entity,
+            camera,
+            image,
+            path: format!("{name}.png"),
+            frames: CAPTURE_DELAY,
+        });
+    }
+}
+
+/// Area around the plot of `graph` holding its title, tick labels and axis titles
+fn export_rect(graph: &GraphWidget) -> Rect {
+    let pos = graph.params.position;
+    let size = graph.params.size;
+    let font_size = graph.params.font_size;
+    Rect {
+        min: Vec2::new(pos.x - 8.0 - font_size * 1.5, pos.y - size.y - 10.0 - font_size * 2.0),
+        max: Vec2::new(pos.x + size.x + 8.0, pos.y + 15.0 + font_size * 0.5),
+    }
+}
+
+/// Lowercase words of `label` joined by dashes, for file names
+fn file_stem(label: &str) -> String {
+    let stem = label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-");
+    if stem.is_empty() { "graph".to_string() } else { stem }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_csv(graph: &GraphWidget, path: &str) {
+    use std::io::Write;
+
+    let result = std::fs::File::create(path).map(std::io::BufWriter::new).and_then(|mut file| {
+        graph.write_csv(&mut file)?;
+        file.flush()
+    });
+    match result {
+        Ok(()) => log::info!("Graph data written to {}", path),
+        Err(error) => log::error!("Failed to write graph data {}: {}", path, error),
+    }
+}
+
+/// Hands the CSV to the browser as a download named `path`
+#[cfg(target_arch = "wasm32")]
+fn write_csv(graph: &GraphWidget, path: &str) {
+    let mut bytes = Vec::new();
+    let result = graph
+        .write_csv(&mut bytes)
+        .map_err(|error| wasm_bindgen::JsValue::from_str(&error.to_string()))
+        .and_then(|()| download_bytes(&bytes, path, "text/csv"));
+    match result {
+        Ok(()) => log::info!("Graph data downloaded as {}", path),
+        Err(error) => log::error!("Failed to download graph data {}: {:?}", path, error),
+    }
+}
+
+/// Exports graphs on F10, or through `GraphExport`: each widget's series as CSV and an
+/// image of it as PNG, rendered offscreen so the window layout does not matter
+pub struct GraphExportPlugin;
+
+impl Plugin for GraphExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphExport>()
+            .add_systems(PreUpdate, request_graph_export.after(bevy::input::InputSystems))
+            .add_systems(PostUpdate, export_graphs);
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::{AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::utils::graph::{draw_graph_widget, GraphWidget};
+use crate::utils::graph_overlay::{layout_screen_graphs, GraphOverlayCamera};
+use crate::utils::histogram::{draw_histogram_widget, HistogramWidget};
+use crate::utils::spectrum::{draw_spectrum_widget, SpectrumWidget};
+
+/// Relative range change per scroll line
+const ZOOM_PER_LINE: f32 = 0.1;
+
+/// Mouse and keyboard state of a frame, shared by all graphs
+struct GraphPointer {
+    /// Cursor in window coordinates
+    cursor: Option<Vec2>,
+    scroll_lines: f32,
+    /// Axes scaled by the wheel, Shift zooms only x and Ctrl only y
+    zoom_axes: BVec2,
+    dragging: bool,
+    drag_started: bool,
+    reset: bool,
+    toggle_freeze: bool,
+}
+
+/// Inputs driving the graphs
+#[derive(SystemParam)]
+pub struct GraphInput<'w, 's> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    mouse_scroll: Res<'w, AccumulatedMouseScroll>,
+    q_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl GraphInput<'_, '_> {
+    fn pointer(&self) -> GraphPointer {
+        // Pixel scrolling (touchpads) is brought back to roughly one line per notch
+        let scroll_lines = match self.mouse_scroll.unit {
+            MouseScrollUnit::Line => self.mouse_scroll.delta.y,
+            MouseScrollUnit::Pixel => self.mouse_scroll.delta.y / 16.0,
+        };
+        let shift = self.keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let ctrl = self.keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        GraphPointer {
+            cursor: self.q_window.iter().next().and_then(|window| window.cursor_position()),
+            scroll_lines,
+            zoom_axes: BVec2::new(!ctrl, !shift),
+            dragging: self.mouse_buttons.pressed(MouseButton::Left),
+            drag_started: self.mouse_buttons.just_pressed(MouseButton::Left),
+            reset: self.mouse_buttons.just_pressed(MouseButton::Right),
+            toggle_freeze: self.keys.just_pressed(KeyCode::KeyF),
+        }
+    }
+}
+
+/// Graph being dragged and the cursor position it was last panned to, in window coordinates
+#[derive(Default)]
+pub struct GraphDrag(Option<(Entity, Vec2)>);
+
+/// System letting the mouse inspect graphs while the simulation runs.
+/// Over a graph: scroll to zoom around the cursor (Shift for x only, Ctrl for y only),
+/// drag with the left button to pan, right click to follow the data again and F to freeze.
+/// The data point nearest the cursor is read out under a crosshair.
+pub fn interact_with_graphs(
+    input: GraphInput,
+    q_camera: Query<(&Camera, &GlobalTransform, Has<GraphOverlayCamera>), With<Camera2d>>,
+    mut drag: Local<GraphDrag>,
+    mut q_graph: Query<(Entity, &mut GraphWidget)>,
+    mut q_spectrum: Query<(Entity, &mut SpectrumWidget)>,
+    mut q_histogram: Query<(Entity, &mut HistogramWidget)>,
+) {
+    let pointer = input.pointer();
+    if !pointer.dragging {
+        drag.0 = None;
+    }
+
+    let camera = |overlay: bool| {
+        q_camera
+            .iter()
+            .find(|&(camera, _, is_overlay)| camera.is_active && is_overlay == overlay)
+            .map(|(camera, transform, _)| (camera, transform))
+    };
+    let (scene, overlay) = (camera(false), camera(true));
+
+    let mut interact = |entity: Entity, graph: &mut GraphWidget| {
+        // Screen-space graphs are drawn by the overlay camera, the others by the scene camera
+        let Some((camera, transform)) = (if graph.params.placement.is_some() { overlay } else { scene }) else {
+            return;
+        };
+        let to_world = |cursor: Vec2| camera.viewport_to_world_2d(transform, cursor).ok();
+        let world = pointer.cursor.and_then(to_world);
+
+        if let Some((dragged, last)) = &mut drag.0
+            && *dragged == entity
+            && let (Some(cursor), Some(world), Some(last_world)) = (pointer.cursor, world, to_world(*last))
+        {
+            graph.pan(last_world, world);
+            *last = cursor;
+        }
+
+        let hovered = world.filter(|&world| graph.contains(world));
+        graph.cursor = hovered;
+        let (Some(world), Some(cursor)) = (hovered, pointer.cursor) else {
+            return;
+        };
+
+        if pointer.scroll_lines != 0.0 {
+            let factor = (1.0 - ZOOM_PER_LINE).powf(pointer.scroll_lines);
+            let factor = Vec2::select(pointer.zoom_axes, Vec2::splat(factor), Vec2::ONE);
+            graph.zoom(graph.from_screen(world), factor);
+        }
+        if pointer.drag_started {
+            drag.0 = Some((entity, cursor));
+        }
+        if pointer.reset {
+            graph.reset_view();
+        }
+        if pointer.toggle_freeze {
+            graph.frozen = !graph.frozen;
+        }
+    };
+
+    for (entity, mut graph) in q_graph.iter_mut() {
+        interact(entity, &mut graph);
+    }
+    for (entity, mut spectrum) in q_spectrum.iter_mut() {
+        interact(entity, &mut spectrum.graph);
+    }
+    for (entity, mut histogram) in q_histogram.iter_mut() {
+        interact(entity, &mut histogram.graph);
+    }
+}
+
+/// Zoom, pan, hover readout and freezing of graphs with the mouse, see `interact_with_graphs`
+pub struct GraphInteractionPlugin;
+
+impl Plugin for GraphInteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            interact_with_graphs
+                .after(layout_screen_graphs)
+                .before(draw_graph_widget)
+                .before(draw_spectrum_widget)
+                .before(draw_histogram_widget),
+        );
+    }
+}
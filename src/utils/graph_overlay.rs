@@ -0,0 +1,166 @@
+use bevy::camera::visibility::RenderLayers;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::view::Hdr;
+
+use crate::utils::graph::{draw_graph_widget, GraphWidget};
+use crate::utils::histogram::{draw_histogram_widget, HistogramWidget};
+use crate::utils::spectrum::{draw_spectrum_widget, SpectrumWidget};
+
+/// Render layer of the overlay camera drawing screen-space graphs
+pub const GRAPH_OVERLAY_LAYER: usize = 7;
+
+/// Order of the overlay camera, after the scene cameras and their overlays
+const GRAPH_OVERLAY_ORDER: isize = 10;
+
+/// Point of the window a screen-space graph is anchored to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphAnchor {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl GraphAnchor {
+    /// Side of the window along each axis: -1 left or bottom, 0 center, 1 right or top
+    fn sides(self) -> Vec2 {
+        match self {
+            GraphAnchor::TopLeft => Vec2::new(-1.0, 1.0),
+            GraphAnchor::TopCenter => Vec2::new(0.0, 1.0),
+            GraphAnchor::TopRight => Vec2::new(1.0, 1.0),
+            GraphAnchor::CenterLeft => Vec2::new(-1.0, 0.0),
+            GraphAnchor::Center => Vec2::new(0.0, 0.0),
+            GraphAnchor::CenterRight => Vec2::new(1.0, 0.0),
+            GraphAnchor::BottomLeft => Vec2::new(-1.0, -1.0),
+            GraphAnchor::BottomCenter => Vec2::new(0.0, -1.0),
+            GraphAnchor::BottomRight => Vec2::new(1.0, -1.0),
+        }
+    }
+}
+
+/// Screen-space placement of a graph, drawn by the overlay camera of `GraphOverlayPlugin`.
+/// The plot area is laid out again whenever the viewport size changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphPlacement {
+    pub anchor: GraphAnchor,
+    /// Distance in pixels from the anchored window edges to the plot area,
+    /// leaving room for the title and axis labels
+    pub margin: Vec2,
+    /// Size of the plot area as a fraction of the viewport
+    pub relative_size: Vec2,
+    /// Bounds of the plot area size, in pixels
+    pub min_size: Vec2,
+    pub max_size: Vec2,
+}
+
+impl Default for GraphPlacement {
+    fn default() -> Self {
+        Self {
+            anchor: GraphAnchor::TopLeft,
+            margin: Vec2::new(30.0, 40.0),
+            relative_size: Vec2::new(0.2, 0.2),
+            min_size: Vec2::new(160.0, 90.0),
+            max_size: Vec2::new(400.0, 240.0),
+        }
+    }
+}
+
+impl GraphPlacement {
+    pub fn new(anchor: GraphAnchor) -> Self {
+        Self { anchor, ..default() }
+    }
+
+    /// Top-left corner and size of the plot area in a viewport of `viewport` pixels,
+    /// in the coordinates of the overlay camera (origin at the center, y up)
+    pub fn rect(&self, viewport: Vec2) -> (Vec2, Vec2) {
+        let size = (self.relative_size * viewport).clamp(self.min_size, self.max_size.max(self.min_size));
+        let sides = self.anchor.sides();
+        // Point of the plot area on the anchor, pulled in from the window edges by the margin
+        let anchored = sides * (viewport / 2.0 - self.margin);
+        let top_left = Vec2::new(
+            anchored.x - (sides.x + 1.0) / 2.0 * size.x,
+            anchored.y + (1.0 - sides.y) / 2.0 * size.y,
+        );
+        (top_left, size)
+    }
+}
+
+/// Camera drawing screen-space graphs over the scene, without bloom
+#[derive(Component)]
+pub struct GraphOverlayCamera;
+
+/// Spawn the overlay camera. It shares the HDR target of the scene cameras when they have one,
+/// which already tonemapped it.
+pub fn spawn_graph_overlay_camera(mut commands: Commands, q_hdr: Query<(), (With<Camera>, With<Hdr>)>) {
+    let mut camera = commands.spawn((
+        Camera2d,
+        Camera {
+            order: GRAPH_OVERLAY_ORDER,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        Tonemapping::None,
+        RenderLayers::layer(GRAPH_OVERLAY_LAYER),
+        GraphOverlayCamera,
+        Name::new("GraphOverlayCamera"),
+    ));
+    if !q_hdr.is_empty() {
+        camera.insert(Hdr);
+    }
+}
+
+/// System placing screen-space graphs in the viewport of the overlay camera
+pub fn layout_screen_graphs(
+    q_camera: Query<&Camera, With<GraphOverlayCamera>>,
+    mut q_graph: Query<&mut GraphWidget>,
+    mut q_spectrum: Query<&mut SpectrumWidget>,
+    mut q_histogram: Query<&mut HistogramWidget>,
+) {
+    let Some(viewport) = q_camera.iter().find_map(|camera| camera.logical_viewport_size()) else {
+        return;
+    };
+    let place = |graph: &mut Mut<GraphWidget>| {
+        let Some(placement) = &graph.params.placement else {
+            return;
+        };
+        let (position, size) = placement.rect(viewport);
+        // Only touch the graph when the viewport changed
+        if graph.params.position != position || graph.params.size != size {
+            graph.params.position = position;
+            graph.params.size = size;
+        }
+    };
+    for mut graph in q_graph.iter_mut() {
+        place(&mut graph);
+    }
+    for spectrum in q_spectrum.iter_mut() {
+        place(&mut spectrum.map_unchanged(|spectrum| &mut spectrum.graph));
+    }
+    for histogram in q_histogram.iter_mut() {
+        place(&mut histogram.map_unchanged(|histogram| &mut histogram.graph));
+    }
+}
+
+/// Draws graphs with a `GraphPlacement` in screen space through their own camera, so they stay
+/// on screen at any window size and are not bloomed. Graphs without one stay in world space.
+pub struct GraphOverlayPlugin;
+
+impl Plugin for GraphOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        // After the scene cameras spawned in `Startup`, to match their HDR setting
+        app.add_systems(PostStartup, spawn_graph_overlay_camera)
+            .add_systems(
+                Update,
+                layout_screen_graphs
+                    .before(draw_graph_widget)
+                    .before(draw_spectrum_widget)
+                    .before(draw_histogram_widget),
+            );
+    }
+}
@@ -0,0 +1,463 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_vector_shapes::prelude::*;
+
+use crate::utils::graph::{
+    draw_single_graph, format_tick, mesh_marks, nice_number, GraphLabel, GraphMeshes, GraphParams, GraphSeries, GraphWidget,
+    GridlineConfig, LabelQuery,
+};
+
+/// Samples held back to fit an automatic range
+const RANGE_FIT_SAMPLES: usize = 256;
+
+/// Scale of the histogram heights
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistogramNormalization {
+    /// Number of samples in each bin
+    Count,
+    /// Fraction of the samples in each bin, the heights sum to 1
+    Probability,
+    /// Probability per unit of the observed scalar, the area sums to 1
+    Density,
+}
+
+/// Gaussian kernel density estimate drawn over the histogram
+#[derive(Debug, Clone)]
+pub struct KdeParams {
+    /// Kernel standard deviation, `None` uses Silverman's rule of thumb
+    pub bandwidth: Option<f32>,
+    pub color: Color,
+}
+
+impl Default for KdeParams {
+    fn default() -> Self {
+        Self {
+            bandwidth: None,
+            color: Color::linear_rgba(0.2, 0.6, 3.0, 1.0),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HistogramParams {
+    /// Layout and styling of the underlying plot, the bars are drawn in `line_color`
+    pub graph: GraphParams,
+    /// Number of bins. An automatic range grows bins of the same width up to twice as many,
+    /// then merges them in pairs.
+    pub bins: usize,
+    /// Range covered by the bins. `None` fits it to the first samples and grows it
+    /// whenever a sample falls outside.
+    pub range: Option<(f32, f32)>,
+    pub normalization: HistogramNormalization,
+    /// Kernel density estimate overlay, `None` draws only the bars
+    pub kde: Option<KdeParams>,
+    /// Redraw the histogram every `update_interval` samples
+    pub update_interval: usize,
+}
+
+impl Default for HistogramParams {
+    fn default() -> Self {
+        Self {
+            graph: GraphParams {
+                label: "Histogram".to_string(),
+                x_gridlines: GridlineConfig::Dynamic {
+                    min_spacing: 0.1,
+                    num_lines: 4,
+                },
+                y_gridlines: GridlineConfig::Dynamic {
+                    min_spacing: 0.01,
+                    num_lines: 4,
+                },
+                show_current_y: false,
+                ..Default::default()
+            },
+            bins: 64,
+            range: None,
+            normalization: HistogramNormalization::Density,
+            kde: None,
+            update_interval: 8,
+        }
+    }
+}
+
+/// Histogram variant of `GraphWidget`: accumulates a scalar over time into bins, for
+/// invariant measures and long-time distributions
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub struct HistogramWidget {
+    pub params: HistogramParams,
+    /// Plot holding the bar outline and the density estimate
+    pub graph: GraphWidget,
+    /// Samples in each bin
+    pub counts: Vec<u64>,
+    /// Range covered by the bins, `None` until the automatic range is fitted
+    pub range: Option<(f32, f32)>,
+    /// Samples outside a fixed range (or too far out to grow an automatic one), left out of the histogram
+    pub outliers: u64,
+    /// Samples in the bins, with their running mean and sum of squared deviations (Welford)
+    total: u64,
+    mean: f64,
+    m2: f64,
+    /// Samples held back until the automatic range is fitted
+    pending: Vec<f32>,
+    since_update: usize,
+}
+
+impl HistogramWidget {
+    pub fn new(mut params: HistogramParams) -> Self {
+        params.bins = params.bins.max(1);
+        let mut series = vec![GraphSeries::new(params.graph.label.clone(), params.graph.line_color)];
+        if let Some(kde) = &params.kde {
+            series.push(GraphSeries::new("KDE", kde.color));
+        }
+
+        Self {
+            graph: GraphWidget::with_series(params.graph.clone(), series),
+            counts: vec![0; params.bins],
+            range: params.range,
+            outliers: 0,
+            total: 0,
+            mean: 0.0,
+            m2: 0.0,
+            pending: Vec::new(),
+            since_update: 0,
+            params,
+        }
+    }
+
+    /// Push a new sample of the observed scalar
+    pub fn add_sample(&mut self, value: f32) {
+        if self.graph.frozen || !value.is_finite() {
+            return;
+        }
+        match self.range {
+            Some(_) => self.bin(value),
+            None => {
+                self.pending.push(value);
+                if self.pending.len() >= RANGE_FIT_SAMPLES {
+                    self.fit_range();
+                }
+            }
+        }
+
+        self.since_update += 1;
+        if self.since_update >= self.params.update_interval.max(1) {
+            self.since_update = 0;
+            self.recompute();
+        }
+    }
+
+    /// Forget all samples, an automatic range is fitted again
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.range = self.params.range;
+        self.outliers = 0;
+        self.total = 0;
+        self.mean = 0.0;
+        self.m2 = 0.0;
+        self.pending.clear();
+        self.recompute();
+    }
+
+    /// Number of samples in the bins
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Mean and standard deviation of the samples in the bins
+    pub fn moments(&self) -> (f32, f32) {
+        let variance = if self.total > 1 { self.m2 / (self.total - 1) as f64 } else { 0.0 };
+        (self.mean as f32, variance.sqrt() as f32)
+    }
+
+    /// Range around the held back samples, padded by half a bin on each side
+    fn fit_range(&mut self) {
+        let min = self.pending.iter().copied().fold(f32::MAX, f32::min);
+        let max = self.pending.iter().copied().fold(f32::MIN, f32::max);
+        let width = if max > min { max - min } else { min.abs().max(1.0) };
+        let pad = 0.5 * width / self.params.bins as f32;
+        self.range = Some((min - pad, max + pad));
+        for value in std::mem::take(&mut self.pending) {
+            self.bin(value);
+        }
+    }
+
+    fn bin(&mut self, value: f32) {
+        let Some((mut min, mut max)) = self.range else {
+            return;
+        };
+        if self.params.range.is_some() {
+            if !(min..=max).contains(&value) {
+                self.outliers += 1;
+                return;
+            }
+        } else if value < min || value > max {
+            // Merged bins at most double the span past the sample, keep the range finite
+            let span = value.max(max) as f64 - value.min(min) as f64;
+            if 4.0 * span > f32::MAX as f64 {
+                self.outliers += 1;
+                return;
+            }
+            // Add bins of the same width towards the sample. Past twice the configured number,
+            // pairs of bins merge into one of twice the width.
+            loop {
+                let width = (max - min) / self.counts.len() as f32;
+                // Counted in f64, a far outlier would overflow the bin count as usize
+                let below = if value < min { ((min - value) as f64 / width as f64).ceil() } else { 0.0 };
+                let above = if value > max { ((value - max) as f64 / width as f64).ceil() } else { 0.0 };
+                if self.counts.len() as f64 + below + above <= 2.0 * self.params.bins as f64 {
+                    let (below, above) = (below as usize, above as usize);
+                    self.counts.splice(0..0, std::iter::repeat_n(0, below));
+                    self.counts.resize(self.counts.len() + above, 0);
+                    min -= below as f32 * width;
+                    max += above as f32 * width;
+                    break;
+                }
+                if self.counts.len() % 2 == 1 {
+                    self.counts.push(0);
+                    max += width;
+                }
+                self.counts = self.counts.chunks(2).map(|pair| pair.iter().sum()).collect();
+            }
+            self.range = Some((min, max));
+        }
+
+        let bins = self.counts.len();
+        let index = (((value - min) / (max - min)) * bins as f32) as usize;
+        self.counts[index.min(bins - 1)] += 1;
+
+        self.total += 1;
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.total as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+    }
+
+    /// Height of a bin holding `count` samples, for the chosen normalization
+    fn height(&self, count: f32, bin_width: f32) -> f32 {
+        let total = self.total.max(1) as f32;
+        match self.params.normalization {
+            HistogramNormalization::Count => count,
+            HistogramNormalization::Probability => count / total,
+            HistogramNormalization::Density => count / (total * bin_width),
+        }
+    }
+
+    /// Rebuild the bar outline, the density estimate and the plot ranges
+    pub fn recompute(&mut self) {
+        for series in self.graph.series.iter_mut() {
+            series.data.clear();
+        }
+        let Some((min, max)) = self.range else {
+            return;
+        };
+        let bins = self.counts.len();
+        let bin_width = (max - min) / bins as f32;
+        let edge = |i: usize| min + i as f32 * bin_width;
+
+        // Outline of the bars, from the baseline up and down each bin
+        let heights: Vec<f32> = self.counts.iter().map(|&count| self.height(count as f32, bin_width)).collect();
+        let outline = &mut self.graph.series[0].data;
+        outline.push_back((edge(0), 0.0));
+        for (i, &height) in heights.iter().enumerate() {
+            outline.push_back((edge(i), height));
+            outline.push_back((edge(i + 1), height));
+        }
+        outline.push_back((edge(bins), 0.0));
+        let mut top = heights.iter().copied().fold(0.0, f32::max);
+
+        // Binned Gaussian KDE, sampled at the bin centers
+        if let Some(kde) = &self.params.kde {
+            let (_, deviation) = self.moments();
+            let silverman = 1.06 * deviation * (self.total.max(1) as f32).powf(-0.2);
+            let bandwidth = kde.bandwidth.unwrap_or(silverman).max(bin_width / 2.0);
+            let center = |i: usize| edge(i) + bin_width / 2.0;
+            let norm = 1.0 / (bandwidth * (2.0 * std::f32::consts::PI).sqrt());
+            let mut estimate = Vec::with_capacity(bins);
+            for i in 0..bins {
+                let x = center(i);
+                let density: f32 = self.counts.iter()
+                    .enumerate()
+                    .filter(|&(_, &count)| count > 0)
+                    .map(|(j, &count)| {
+                        let u = (x - center(j)) / bandwidth;
+                        count as f32 * norm * (-0.5 * u * u).exp()
+                    })
+                    .sum();
+                // Density times bin width is a count, scaled like the bars
+                estimate.push((x, self.height(density * bin_width, bin_width)));
+            }
+            top = estimate.iter().map(|&(_, y)| y).fold(top, f32::max);
+            self.graph.series[1].data.extend(estimate);
+        }
+
+        self.graph.x_min = min;
+        self.graph.x_max = max;
+        self.graph.y_min = 0.0;
+        self.graph.y_max = if top > 0.0 { top * 1.1 } else { 1.0 };
+    }
+}
+
+/// System to draw the histogram widgets and their sample statistics
+pub fn draw_histogram_widget(
+    mut commands: Commands,
+    mut painter: ShapePainter,
+    mut meshes: GraphMeshes,
+    mut query: Query<(Entity, &mut HistogramWidget)>,
+    mut q_label: LabelQuery,
+) {
+    for (entity, mut histogram) in query.iter_mut() {
+        let histogram = &mut *histogram;
+        let mut layout = draw_single_graph(&mut painter, &histogram.graph);
+
+        // Sample count and moments in the top right corner
+        let graph = &histogram.graph;
+        if histogram.total > 0 {
+            let (mean, deviation) = histogram.moments();
+            // Two significant digits of the deviation
+            let precision = nice_number(deviation / 10.0, false);
+            layout.labels.push(GraphLabel {
+                text: format!(
+                    "n = {}  mean {}  sd {}",
+                    histogram.total,
+                    format_tick(mean, precision),
+                    format_tick(deviation, precision)
+                ),
+                position: Vec2::new(graph.params.position.x + graph.params.size.x - 5.0, graph.params.position.y + 15.0),
+                font_size: graph.params.font_size * 0.8,
+                color: graph.params.text_color,
+                anchor: Anchor::TOP_RIGHT,
+                rotation: 0.0,
+            });
+        }
+
+        let layers = histogram.graph.render_layers();
+        let mesh_marks = mesh_marks(&histogram.graph, &layout);
+        histogram.graph.series_mesh.sync(&mut commands, &mut meshes, entity, mesh_marks, &layers);
+        histogram.graph.labels.sync(&mut commands, entity, &layout.labels, &layers, &mut q_label);
+    }
+}
+
+/// Spawn a histogram widget entity
+pub fn spawn_histogram_widget(
+    commands: &mut Commands,
+    params: HistogramParams,
+) -> Entity {
+    commands.spawn((
+        HistogramWidget::new(params),
+        Name::new("HistogramWidget"),
+    )).id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(bins: usize, range: Option<(f32, f32)>) -> HistogramWidget {
+        HistogramWidget::new(HistogramParams { bins, range, ..default() })
+    }
+
+    /// Deterministic samples spread over [0, 1)
+    fn samples(count: usize) -> impl Iterator<Item = f32> {
+        (0..count).map(|i| (i as f32 * 0.618_034).fract())
+    }
+
+    #[test]
+    fn automatic_range_fits_the_first_samples() {
+        let mut histogram = histogram(16, None);
+        for value in samples(RANGE_FIT_SAMPLES - 1) {
+            histogram.add_sample(value);
+        }
+        assert!(histogram.range.is_none());
+        assert_eq!(histogram.total(), 0);
+
+        histogram.add_sample(0.5);
+        let (min, max) = histogram.range.unwrap();
+        assert!(min < 0.0 && max > 0.99 && max < 1.1, "range {:?}", (min, max));
+        assert_eq!(histogram.total(), RANGE_FIT_SAMPLES as u64);
+        assert_eq!(histogram.counts.iter().sum::<u64>(), RANGE_FIT_SAMPLES as u64);
+    }
+
+    #[test]
+    fn automatic_range_grows_then_merges_bins() {
+        let mut histogram = histogram(16, None);
+        for value in samples(RANGE_FIT_SAMPLES) {
+            histogram.add_sample(value);
+        }
+        let (min, max) = histogram.range.unwrap();
+        let width = (max - min) / 16.0;
+
+        // A sample just past the range adds bins of the same width
+        histogram.add_sample(max + 1.5 * width);
+        let (grown_min, grown_max) = histogram.range.unwrap();
+        assert_eq!(histogram.counts.len(), 18);
+        assert_eq!(grown_min, min);
+        assert!((grown_max - (max + 2.0 * width)).abs() < 1e-5);
+
+        // A far one merges pairs of bins until it fits in twice the configured count
+        histogram.add_sample(min - 100.0 * width);
+        let (merged_min, merged_max) = histogram.range.unwrap();
+        assert!(histogram.counts.len() <= 32);
+        assert!(merged_min <= min - 100.0 * width && merged_max >= grown_max);
+        assert_eq!(histogram.counts.iter().sum::<u64>(), histogram.total());
+        assert_eq!(histogram.total(), RANGE_FIT_SAMPLES as u64 + 2);
+    }
+
+    #[test]
+    fn far_outliers_keep_the_range_finite() {
+        let mut histogram = histogram(16, None);
+        for value in samples(RANGE_FIT_SAMPLES) {
+            histogram.add_sample(value);
+        }
+        histogram.add_sample(f32::MAX);
+        histogram.add_sample(-1.0e38);
+        let (min, max) = histogram.range.unwrap();
+        assert!(min.is_finite() && max.is_finite() && (max - min).is_finite());
+        assert_eq!(histogram.outliers, 2);
+        assert_eq!(histogram.total(), RANGE_FIT_SAMPLES as u64);
+    }
+
+    #[test]
+    fn fixed_range_counts_outliers() {
+        let mut histogram = histogram(10, Some((0.0, 1.0)));
+        for value in [-0.5, 0.0, 0.05, 0.95, 1.0, 1.5] {
+            histogram.add_sample(value);
+        }
+        assert_eq!(histogram.outliers, 2);
+        assert_eq!(histogram.counts[0], 2);
+        assert_eq!(histogram.counts[9], 2);
+    }
+
+    #[test]
+    fn moments_match_the_samples() {
+        let mut histogram = histogram(32, Some((-10.0, 10.0)));
+        let values: Vec<f32> = samples(1000).map(|x| 3.0 * x - 1.0).collect();
+        for &value in &values {
+            histogram.add_sample(value);
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let variance = values.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        let (m, sd) = histogram.moments();
+        assert!((m as f64 - mean).abs() < 1e-5, "mean {} vs {}", m, mean);
+        assert!((sd as f64 - variance.sqrt()).abs() < 1e-5, "sd {} vs {}", sd, variance.sqrt());
+    }
+
+    #[test]
+    fn density_integrates_to_one() {
+        for normalization in [HistogramNormalization::Density, HistogramNormalization::Probability] {
+            let mut histogram = HistogramWidget::new(HistogramParams { bins: 20, normalization, ..default() });
+            for value in samples(5000).map(|x| x * x * 4.0 - 1.0) {
+                histogram.add_sample(value);
+            }
+            histogram.recompute();
+            // The outline runs up and down each bin, every bin gives a (left, height), (right, height) pair
+            let outline: Vec<(f32, f32)> = histogram.graph.series[0].data.iter().copied().collect();
+            let bars = outline[1..outline.len() - 1].chunks(2);
+            let (area, sum) = bars.fold((0.0, 0.0), |(area, sum), bar| {
+                (area + (bar[1].0 - bar[0].0) * bar[0].1, sum + bar[0].1)
+            });
+            let total = if normalization == HistogramNormalization::Density { area } else { sum };
+            assert!((total - 1.0).abs() < 1e-4, "{:?} sums to {}", normalization, total);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::utils::ODEs::ODEFunc;
+
+/// One-step integration scheme advancing a state vector in place
+pub trait Integrator {
+    /// Advance `y` from `t` to `t + dt`
+    fn step(&mut self, ode: &dyn ODEFunc, t: f32, dt: f32, y: &mut Vec<f32>);
+}
+
+/// First order explicit (forward) Euler scheme
+#[derive(Default)]
+pub struct ExplicitEuler {
+    k: Vec<f32>,
+}
+
+impl ExplicitEuler {
+    pub fn new(n: usize) -> Self {
+        Self { k: vec![0.0; n] }
+    }
+}
+
+impl Integrator for ExplicitEuler {
+    fn step(&mut self, ode: &dyn ODEFunc, t: f32, dt: f32, y: &mut Vec<f32>) {
+        self.k.resize(y.len(), 0.0);
+        ode.call(t, y, &mut self.k);
+        for (yi, ki) in y.iter_mut().zip(&self.k) {
+            *yi += dt * ki;
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::utils::graph::{GraphParams, GraphWidget};
+
+/// Curve mapping a progress in [0, 1] to a factor, usually in [0, 1].
+/// Ribbons evaluate it with 0 at the tail and 1 at the head.
+#[derive(Clone)]
+pub enum InterpolationType {
+    Linear,
+    /// `x^power`
+    Poly(f32),
+    /// `(e^(k x) - 1) / (e^k - 1)`, slow start for k > 0 and fast start for k < 0
+    Exponential(f32),
+    /// Hermite `3x² - 2x³`
+    Smoothstep,
+    /// Cubic ease-in `x³`
+    EaseIn,
+    /// Cubic ease-out `1 - (1 - x)³`
+    EaseOut,
+    /// Cubic ease-in for the first half, ease-out for the second
+    EaseInOut,
+    /// Piecewise linear through `(x, value)` keyframes sorted by `x`, constant outside them
+    Keyframes(Vec<(f32, f32)>),
+    Custom(Arc<dyn Fn(f32) -> f32 + Send + Sync>),
+}
+
+impl InterpolationType {
+    /// Wrap a closure as a curve
+    pub fn custom(f: impl Fn(f32) -> f32 + Send + Sync + 'static) -> Self {
+        InterpolationType::Custom(Arc::new(f))
+    }
+
+    /// Value of the curve at `x`, clamped to [0, 1]
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            InterpolationType::Linear => x,
+            InterpolationType::Poly(power) => x.powf(*power),
+            InterpolationType::Exponential(k) => {
+                if k.abs() < 1e-4 {
+                    x
+                } else {
+                    (k * x).exp_m1() / k.exp_m1()
+                }
+            }
+            InterpolationType::Smoothstep => x * x * (3.0 - 2.0 * x),
+            InterpolationType::EaseIn => x * x * x,
+            InterpolationType::EaseOut => 1.0 - (1.0 - x).powi(3),
+            InterpolationType::EaseInOut => {
+                if x < 0.5 {
+                    4.0 * x * x * x
+                } else {
+                    1.0 - (2.0 - 2.0 * x).powi(3) / 2.0
+                }
+            }
+            InterpolationType::Keyframes(keys) => evaluate_keyframes(keys, x),
+            InterpolationType::Custom(f) => f(x),
+        }
+    }
+
+    /// `samples` evenly spaced `(x, value)` points over [0, 1]
+    pub fn preview(&self, samples: usize) -> Vec<(f32, f32)> {
+        let last = samples.max(2) - 1;
+        (0..=last)
+            .map(|i| {
+                let x = i as f32 / last as f32;
+                (x, self.evaluate(x))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for InterpolationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpolationType::Linear => write!(f, "Linear"),
+            InterpolationType::Poly(power) => write!(f, "Poly({})", power),
+            InterpolationType::Exponential(k) => write!(f, "Exponential({})", k),
+            InterpolationType::Smoothstep => write!(f, "Smoothstep"),
+            InterpolationType::EaseIn => write!(f, "EaseIn"),
+            InterpolationType::EaseOut => write!(f, "EaseOut"),
+            InterpolationType::EaseInOut => write!(f, "EaseInOut"),
+            InterpolationType::Keyframes(keys) => write!(f, "Keyframes({:?})", keys),
+            InterpolationType::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// Custom curves are equal when they share the same closure
+impl PartialEq for InterpolationType {
+    fn eq(&self, other: &Self) -> bool {
+        use InterpolationType::*;
+        match (self, other) {
+            (Linear, Linear) | (Smoothstep, Smoothstep) | (EaseIn, EaseIn) | (EaseOut, EaseOut) | (EaseInOut, EaseInOut) => true,
+            (Poly(a), Poly(b)) | (Exponential(a), Exponential(b)) => a == b,
+            (Keyframes(a), Keyframes(b)) => a == b,
+            (Custom(a), Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+fn evaluate_keyframes(keys: &[(f32, f32)], x: f32) -> f32 {
+    let (Some(first), Some(last)) = (keys.first(), keys.last()) else {
+        return x;
+    };
+    if x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+    for pair in keys.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if x <= x1 {
+            if x1 - x0 <= f32::EPSILON {
+                return y1;
+            }
+            return y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+        }
+    }
+    last.1
+}
+
+/// Spawn a graph widget showing `curve` over [0, 1], drawn by `draw_graph_widget`
+pub fn spawn_curve_preview(
+    commands: &mut Commands,
+    curve: &InterpolationType,
+    params: GraphParams,
+) -> Entity {
+    let mut graph = GraphWidget::new(params);
+    for (x, y) in curve.preview(graph.params.max_points) {
+        graph.add_point(x, y);
+    }
+    commands.spawn((
+        graph,
+        Name::new(format!("CurvePreview {:?}", curve)),
+    )).id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curves() -> Vec<InterpolationType> {
+        vec![
+            InterpolationType::Linear,
+            InterpolationType::Poly(0.5),
+            InterpolationType::Poly(3.0),
+            InterpolationType::Exponential(4.0),
+            InterpolationType::Exponential(-4.0),
+            InterpolationType::Exponential(0.0),
+            InterpolationType::Smoothstep,
+            InterpolationType::EaseIn,
+            InterpolationType::EaseOut,
+            InterpolationType::EaseInOut,
+            InterpolationType::Keyframes(vec![(0.0, 0.0), (0.3, 0.2), (0.3, 0.5), (1.0, 1.0)]),
+        ]
+    }
+
+    #[test]
+    fn curves_run_from_zero_to_one() {
+        for curve in curves() {
+            assert!(curve.evaluate(0.0).abs() < 1e-6, "{:?} starts at {}", curve, curve.evaluate(0.0));
+            assert!((curve.evaluate(1.0) - 1.0).abs() < 1e-6, "{:?} ends at {}", curve, curve.evaluate(1.0));
+            // Progress is clamped
+            assert_eq!(curve.evaluate(-1.0), curve.evaluate(0.0));
+            assert_eq!(curve.evaluate(2.0), curve.evaluate(1.0));
+        }
+    }
+
+    #[test]
+    fn curves_are_monotonic() {
+        for curve in curves() {
+            let preview = curve.preview(1000);
+            assert_eq!(preview.len(), 1000);
+            for pair in preview.windows(2) {
+                assert!(pair[1].1 >= pair[0].1, "{:?} decreases between {:?} and {:?}", curve, pair[0], pair[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn ease_in_out_is_continuous_at_the_midpoint() {
+        let curve = InterpolationType::EaseInOut;
+        assert!((curve.evaluate(0.5) - 0.5).abs() < 1e-6);
+        assert!((curve.evaluate(0.5 - 1e-4) - curve.evaluate(0.5 + 1e-4)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_hold() {
+        let curve = InterpolationType::Keyframes(vec![(0.2, 0.4), (0.6, 0.8)]);
+        assert_eq!(curve.evaluate(0.0), 0.4);
+        assert!((curve.evaluate(0.4) - 0.6).abs() < 1e-6);
+        assert_eq!(curve.evaluate(1.0), 0.8);
+        // Without keyframes the curve is linear
+        assert_eq!(InterpolationType::Keyframes(Vec::new()).evaluate(0.3), 0.3);
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::utils::graph::{GraphParams, GraphWidget};
+
+/// Conserved quantities of a model (energy, angular momentum, Casimirs, ...)
+pub trait Invariant {
+    /// Names of the conserved quantities, in the order written by `invariants`
+    fn invariant_names(&self) -> Vec<&'static str>;
+    /// Evaluate every conserved quantity at state `y`
+    fn invariants(&self, y: &[f32], out: &mut Vec<f32>);
+}
+
+/// Resources exposing the current state vector of a running simulation
+pub trait SimulationState: Resource {
+    fn state(&self) -> Vec<f32>;
+    /// Simulated time of the state, the drift is plotted against it
+    fn time(&self) -> f32;
+}
+
+/// Tracks the relative drift of one conserved quantity, plotted by the `GraphWidget` on the same entity
+#[derive(Component)]
+pub struct InvariantMonitor {
+    pub model: Box<dyn Invariant + Send + Sync>,
+    /// Index of the monitored quantity in the model's `invariants`
+    pub index: usize,
+    /// Value at the first sample, drift is measured against it
+    pub reference: Option<f32>,
+    /// Latest relative drift `(value - reference) / |reference|`
+    pub drift: f32,
+    /// Largest absolute relative drift seen so far
+    pub max_drift: f32,
+    /// Log the drift every `log_interval` simulated seconds (0 disables logging)
+    pub log_interval: f32,
+    last_log: f32,
+    values: Vec<f32>,
+}
+
+impl InvariantMonitor {
+    pub fn new(model: Box<dyn Invariant + Send + Sync>, index: usize) -> Self {
+        Self {
+            model,
+            index,
+            reference: None,
+            drift: 0.0,
+            max_drift: 0.0,
+            log_interval: 5.0,
+            last_log: 0.0,
+            values: Vec::new(),
+        }
+    }
+
+    /// Name of the monitored quantity
+    pub fn name(&self) -> &'static str {
+        self.model.invariant_names().get(self.index).copied().unwrap_or("invariant")
+    }
+
+    /// Evaluate the quantity at state `y` and return its relative drift
+    pub fn record(&mut self, y: &[f32]) -> f32 {
+        self.model.invariants(y, &mut self.values);
+        let value = self.values[self.index];
+        let reference = *self.reference.get_or_insert(value);
+
+        // Fall back to the absolute drift when the reference is (close to) zero
+        self.drift = if reference.abs() > f32::EPSILON {
+            (value - reference) / reference.abs()
+        } else {
+            value - reference
+        };
+        self.max_drift = self.max_drift.max(self.drift.abs());
+        self.drift
+    }
+}
+
+/// System sampling every monitor from the simulation state `S`, run it in `FixedUpdate` after the step
+pub fn monitor_invariants<S: SimulationState>(
+    state: Res<S>,
+    mut query: Query<(&mut InvariantMonitor, &mut GraphWidget)>,
+) {
+    let y = state.state();
+    let time = state.time();
+
+    for (mut monitor, mut graph) in query.iter_mut() {
+        let drift = monitor.record(&y);
+        // Plotted in percent so typical drifts stay readable on the axis labels
+        graph.add_point(time, 100.0 * drift);
+
+        if monitor.log_interval > 0.0 && time - monitor.last_log >= monitor.log_interval {
+            monitor.last_log = time;
+            log::info!(
+                "{} relative drift: {:+.3e} (max {:.3e}) at t = {:.1}s",
+                monitor.name(),
+                drift,
+                monitor.max_drift,
+                time
+            );
+        }
+    }
+}
+
+/// Spawn a graph widget plotting the relative drift (in %) of the `index`-th invariant of `model`
+pub fn spawn_invariant_monitor(
+    commands: &mut Commands,
+    model: Box<dyn Invariant + Send + Sync>,
+    index: usize,
+    params: GraphParams,
+) -> Entity {
+    commands.spawn((
+        GraphWidget::new(params),
+        InvariantMonitor::new(model, index),
+        Name::new("InvariantMonitor"),
+    )).id()
+}
@@ -1,22 +1,158 @@
-use bevy::mesh::Indices;
+use bevy::asset::{embedded_asset, RenderAssetUsages};
+use bevy::camera::visibility::NoFrustumCulling;
+use bevy::mesh::{Indices, MeshVertexAttribute, MeshVertexBufferLayoutRef, VertexAttributeValues};
+use bevy::pbr::{Material, MaterialPipeline, MaterialPipelineKey, MaterialPlugin};
 use bevy::prelude::*;
-use bevy::render::render_resource::PrimitiveTopology;
-use bevy::asset::RenderAssetUsages;
+use bevy::reflect::TypePath;
+use bevy::render::render_resource::{
+    AsBindGroup, PrimitiveTopology, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipelineError, VertexFormat,
+};
+use bevy::shader::ShaderRef;
+use bevy::sprite_render::{AlphaMode2d, Material2d, Material2dKey, Material2dPlugin};
+use bevy::transform::helper::TransformHelper;
 use std::collections::VecDeque;
 
-#[derive(Debug, Clone)]
-pub enum InterpolationType {
-    Poly(f32),
+use crate::utils::colorbar::update_colorbar;
+use crate::utils::colormap::Colormap;
+use crate::utils::decimation::ramer_douglas_peucker;
+pub use crate::utils::interpolation::InterpolationType;
+
+/// Shader computing the ribbon width, fade and color from the age of each vertex.
+/// Embedded in the binary by `MeshRibbonPlugin`, so apps (and wasm bundles) need no assets folder.
+pub const RIBBON_SHADER_PATH: &str = "embedded://PhyzViz/utils/shaders/ribbon.wgsl";
+
+/// Direction the vertex is pushed along in 2D, or the ribbon tangent in 3D (xyz),
+/// and which side of the ribbon the vertex is on (w = ±1)
+pub const ATTRIBUTE_RIBBON_SIDE: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_RibbonSide", 1_872_402_113, VertexFormat::Float32x4);
+
+/// Simulated time the vertex was sampled at, its age is `head - birth`
+pub const ATTRIBUTE_RIBBON_BIRTH: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_RibbonBirth", 1_872_402_114, VertexFormat::Float32);
+
+/// Distance travelled along the ribbon up to the vertex's sample
+pub const ATTRIBUTE_RIBBON_ARC_LENGTH: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_RibbonArcLength", 1_872_402_115, VertexFormat::Float32);
+
+/// Number of samples of the width, alpha and color curves sent to the shader
+pub const CURVE_LUT_SIZE: usize = 32;
+
+/// Scalar carried by the vertex's sample (speed, energy...), see `GradientAxis::Scalar`
+pub const ATTRIBUTE_RIBBON_SCALAR: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_RibbonScalar", 1_872_402_116, VertexFormat::Float32);
+
+/// Maximum number of colormap stops sent to the shader, longer colormaps are resampled
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
+/// Shader def selecting the camera-facing geometry in `RIBBON_SHADER_PATH`
+const RIBBON_3D_DEF: &str = "RIBBON_3D";
+
+/// Birth given to slots that were never written, old enough to be invisible
+const UNWRITTEN_BIRTH: f32 = -1.0e9;
+
+/// Samples closer than this to the head only move it
+const MIN_SAMPLE_DISTANCE: f32 = 1.0e-3;
+
+/// Change of the pixel size, either way, after which the whole history is decimated again
+const LOD_RATIO: f32 = 1.5;
+
+/// Quantity the colormap is sampled along, 0 at the head of the ribbon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientAxis {
+    /// Age of the sample relative to `lifetime`
+    Age,
+    /// Distance to the head, the colormap spans `length` world units and clamps after
+    ArcLength(f32),
+    /// Scalar stored with each sample (`MeshRibbon::current_scalar`), normalized by `scalar_range`
+    /// with the first stop at the low end
+    Scalar,
+}
+
+/// How consecutive segments of a 2D ribbon are joined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinStyle {
+    /// Offset along the bisector, stretched so both edges keep the ribbon width,
+    /// up to `limit` times the half width on sharp turns
+    Miter { limit: f32 },
+    /// Offset along the bisector at the half width, the join vertex lies on the arc
+    /// of a round join (with `subdivisions` the arc is sampled by the spline points)
+    Round,
+}
+
+/// When the head of a ribbon is kept as a sample and a new head is started.
+/// Until then the head follows the target, so the ribbon never lags behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSpacing {
+    /// One sample per fixed step
+    EveryStep,
+    /// Simulated seconds between samples
+    Interval(f32),
+    /// Distance between samples
+    ArcLength(f32),
+    /// New sample once the motion deviates by `max_angle` radians from the straight line
+    /// since the last sample, or after `max_length`, so straight stretches use few samples
+    Curvature { max_angle: f32, max_length: f32 },
+}
+
+/// Ramer–Douglas–Peucker decimation of the older samples of a ribbon.
+/// All samples are kept on the CPU, only the drawn ones are decimated, so zooming in
+/// brings the detail back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decimation {
+    /// Maximum distance in screen pixels between the drawn ribbon and its samples
+    pub tolerance: f32,
+    /// Newest samples always drawn at full resolution, older ones are decimated in chunks this long
+    pub recent: usize,
+    /// Samples held by the mesh, fixed at spawn. The oldest drawn samples are dropped past it.
+    pub max_drawn: usize,
+}
+
+impl Default for Decimation {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.5,
+            recent: 1024,
+            max_drawn: 20_000,
+        }
+    }
+}
+
+/// Range of the scalar mapped onto the colormap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScalarRange {
+    /// Minimum and maximum of the scalars currently stored in the ribbon
+    Auto,
+    Fixed(f32, f32),
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct MeshRibbonParams {
     pub width: f32,
+    /// Samples held by the ring, fixed at spawn. Must cover `lifetime` at the sampling rate,
+    /// or the tail is cut before it fades out.
     pub max_points: usize,
+    /// Simulated seconds after which a sample has faded out
+    pub lifetime: f32,
+    pub spacing: SampleSpacing,
+    /// Decimation of long histories, `None` draws every sample
+    pub decimation: Option<Decimation>,
     pub color: Color,
     pub fade_to_transparent: bool,
+    /// Points resampled on a Catmull–Rom spline between consecutive samples (1 joins them straight),
+    /// fixed at spawn
+    pub subdivisions: usize,
+    pub join: JoinStyle,
     pub width_variation: InterpolationType,
     pub transparency_variance: InterpolationType,
+    /// Maps the progress along the gradient axis (1 at the head) to 1 minus the colormap position.
+    /// With `GradientAxis::Scalar` it maps the normalized scalar to the colormap position.
+    pub color_variation: InterpolationType,
+    /// Gradient multiplied with `color`, first stop at the head
+    pub colormap: Colormap,
+    pub gradient_axis: GradientAxis,
+    pub scalar_range: ScalarRange,
+    /// Multiplier on the color, values above 1 feed the bloom pass
+    pub glow: f32,
 }
 
 impl Default for MeshRibbonParams {
@@ -24,174 +160,1071 @@ impl Default for MeshRibbonParams {
         Self {
             width: 0.1,
             max_points: 100,
+            lifetime: 1.0,
+            spacing: SampleSpacing::EveryStep,
+            decimation: None,
             color: Color::srgb(1.0, 0.3, 0.1),
             fade_to_transparent: true,
+            subdivisions: 4,
+            join: JoinStyle::Miter { limit: 4.0 },
             width_variation: InterpolationType::Poly(2.0),
             transparency_variance: InterpolationType::Poly(10.0),
+            color_variation: InterpolationType::Linear,
+            colormap: Colormap::default(),
+            gradient_axis: GradientAxis::Age,
+            scalar_range: ScalarRange::Auto,
+            glow: 1.0,
+        }
+    }
+}
+
+/// Uniforms of `RibbonMaterial`, mirrored by `RibbonSettings` in the shader
+#[derive(ShaderType, Debug, Clone, Copy, PartialEq)]
+pub struct RibbonSettings {
+    pub color: Vec4,
+    pub gradient: [Vec4; MAX_GRADIENT_STOPS],
+    pub gradient_len: u32,
+    /// 0 for `GradientAxis::Age`, 1 for `GradientAxis::ArcLength`, 2 for `GradientAxis::Scalar`
+    pub gradient_axis: u32,
+    pub gradient_length: f32,
+    pub scalar_min: f32,
+    pub scalar_max: f32,
+    pub glow: f32,
+    /// Simulated time of the last sampling
+    pub head: f32,
+    /// Arc length of the newest sample
+    pub head_arc_length: f32,
+    /// Age (in simulated seconds) at which the ribbon has faded out completely
+    pub max_age: f32,
+    pub width: f32,
+    /// Width, alpha and color curves sampled evenly from the tail (0) to the head (1), w unused
+    pub curves: [Vec4; CURVE_LUT_SIZE],
+    /// Non-zero to fade the alpha along the ribbon
+    pub fade: u32,
+}
+
+impl RibbonSettings {
+    pub fn from_params(params: &MeshRibbonParams) -> Self {
+        let (gradient, gradient_len) = params.colormap.to_uniform::<MAX_GRADIENT_STOPS>();
+        let (gradient_axis, gradient_length) = match params.gradient_axis {
+            GradientAxis::Age => (0, 1.0),
+            GradientAxis::ArcLength(length) => (1, length.max(f32::EPSILON)),
+            GradientAxis::Scalar => (2, 1.0),
+        };
+        let (scalar_min, scalar_max) = match params.scalar_range {
+            ScalarRange::Auto => (0.0, 1.0),
+            ScalarRange::Fixed(min, max) => (min, max),
+        };
+        Self {
+            color: params.color.to_linear().to_vec4(),
+            gradient,
+            gradient_len,
+            gradient_axis,
+            gradient_length,
+            scalar_min,
+            scalar_max,
+            glow: params.glow,
+            head: 0.0,
+            head_arc_length: 0.0,
+            max_age: params.lifetime.max(f32::EPSILON),
+            width: params.width,
+            curves: bake_curves(params),
+            fade: params.fade_to_transparent as u32,
+        }
+    }
+
+    /// 0 at the tail, 1 at the head, as `ribbon_progress` in the shader
+    pub fn progress(&self, birth: f32) -> f32 {
+        (1.0 - (self.head - birth) / self.max_age).clamp(0.0, 1.0)
+    }
+
+    /// Linear interpolation in the curve lookup table, as `lookup_curves` in the shader
+    pub fn lookup_curves(&self, t: f32) -> Vec4 {
+        let x = t.clamp(0.0, 1.0) * (CURVE_LUT_SIZE - 1) as f32;
+        let i = (x.floor() as usize).min(CURVE_LUT_SIZE - 2);
+        self.curves[i].lerp(self.curves[i + 1], x - i as f32)
+    }
+
+    /// Curves at `progress`, zero past the tail, as `sample_curves` in the shader
+    pub fn sample_curves(&self, progress: f32) -> Vec4 {
+        if progress <= 0.0 { Vec4::ZERO } else { self.lookup_curves(progress) }
+    }
+
+    /// Linear interpolation between the evenly spaced gradient stops, as `sample_gradient` in the shader
+    pub fn sample_gradient(&self, t: f32) -> Vec4 {
+        if self.gradient_len <= 1 {
+            return self.gradient[0];
+        }
+        let last = self.gradient_len as usize - 1;
+        let x = t.clamp(0.0, 1.0) * last as f32;
+        let i = (x.floor() as usize).min(last - 1);
+        self.gradient[i].lerp(self.gradient[i + 1], x - i as f32)
+    }
+
+    /// Half width and linear color of a vertex, as the ribbon vertex and fragment shaders compute them
+    pub fn vertex_style(&self, birth: f32, arc_length: f32, scalar: f32) -> (f32, Vec4) {
+        let progress = self.progress(birth);
+        let half_width = 0.5 * self.width * self.sample_curves(progress).x;
+        let gradient_t = match self.gradient_axis {
+            2 => {
+                let span = (self.scalar_max - self.scalar_min).max(1.0e-6);
+                self.lookup_curves((scalar - self.scalar_min) / span).z
+            }
+            1 => {
+                let along = (self.head_arc_length - arc_length) / self.gradient_length;
+                1.0 - self.lookup_curves(1.0 - along.clamp(0.0, 1.0)).z
+            }
+            _ => 1.0 - self.lookup_curves(progress).z,
+        };
+        let alpha = if self.fade != 0 { self.sample_curves(progress).y / 4.0 } else { 0.25 };
+        let tint = self.color * self.sample_gradient(gradient_t);
+        (half_width, (tint.truncate() * self.glow).extend(tint.w * alpha))
+    }
+}
+
+/// Samples the ribbon curves into the shader lookup table
+fn bake_curves(params: &MeshRibbonParams) -> [Vec4; CURVE_LUT_SIZE] {
+    let mut curves = [Vec4::ZERO; CURVE_LUT_SIZE];
+    for (i, sample) in curves.iter_mut().enumerate() {
+        let x = i as f32 / (CURVE_LUT_SIZE - 1) as f32;
+        *sample = Vec4::new(
+            params.width_variation.evaluate(x),
+            params.transparency_variance.evaluate(x),
+            params.color_variation.evaluate(x),
+            0.0,
+        );
+    }
+    curves
+}
+
+/// Material drawing `MeshRibbon` meshes, width, fade and color are computed in the shader
+/// from the age and arc length of each vertex so tuning them never touches the mesh
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct RibbonMaterial {
+    #[uniform(0)]
+    pub settings: RibbonSettings,
+}
+
+impl Material2d for RibbonMaterial {
+    fn vertex_shader() -> ShaderRef {
+        RIBBON_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        RIBBON_SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_RIBBON_SIDE.at_shader_location(1),
+            ATTRIBUTE_RIBBON_BIRTH.at_shader_location(2),
+            ATTRIBUTE_RIBBON_ARC_LENGTH.at_shader_location(3),
+            ATTRIBUTE_RIBBON_SCALAR.at_shader_location(4),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// 3D counterpart of `RibbonMaterial`, the ribbon is turned towards the camera around its tangent.
+/// Register with `MaterialPlugin` with the prepass and shadows disabled.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct RibbonMaterial3d {
+    #[uniform(0)]
+    pub settings: RibbonSettings,
+}
+
+impl Material for RibbonMaterial3d {
+    fn vertex_shader() -> ShaderRef {
+        RIBBON_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        RIBBON_SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_RIBBON_SIDE.at_shader_location(1),
+            ATTRIBUTE_RIBBON_BIRTH.at_shader_location(2),
+            ATTRIBUTE_RIBBON_ARC_LENGTH.at_shader_location(3),
+            ATTRIBUTE_RIBBON_SCALAR.at_shader_location(4),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.vertex.shader_defs.push(RIBBON_3D_DEF.into());
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.push(RIBBON_3D_DEF.into());
         }
+        descriptor.primitive.cull_mode = None;
+        Ok(())
     }
 }
 
+/// Material of a ribbon, which also decides how its mesh is laid out
+#[derive(Clone, Debug)]
+pub enum RibbonMaterialHandle {
+    /// Flat in the XY plane, drawn by a `Camera2d`
+    Flat(Handle<RibbonMaterial>),
+    /// Camera-facing in 3D, drawn by a `Camera3d`
+    Billboard(Handle<RibbonMaterial3d>),
+}
+
 #[derive(Component)]
 pub struct MeshRibbon {
     pub params: MeshRibbonParams,
     pub positions: VecDeque<Vec3>,
+    /// Scalar of each sample, parallel to `positions`
+    pub scalars: VecDeque<f32>,
+    /// Simulated time of each sample, parallel to `positions`
+    pub times: VecDeque<f32>,
     pub mesh_handle: Handle<Mesh>,
+    pub material_handle: RibbonMaterialHandle,
     pub current_position: Vec3, // Track separately from Transform
+    /// Scalar recorded with the next sample, for `GradientAxis::Scalar`
+    pub current_scalar: f32,
+    /// Distance travelled by the newest sample since spawn
+    pub arc_length: f32,
+    /// Distance travelled by each sample since spawn, parallel to `positions`
+    arc_lengths: VecDeque<f32>,
+    /// Number of samples pushed since spawn, a sample's global index is its push order
+    pushed: usize,
+    capacity: usize,
+    subdivisions: usize,
+    /// Global indices of the samples drawn by the mesh, all of them without decimation.
+    /// Drawn sample `n` (counted since the last rebuild) owns the ring points
+    /// `n * subdivisions..(n + 1) * subdivisions` modulo `num_points()`
+    drawn: VecDeque<usize>,
+    /// Number of drawn samples written since the last rebuild
+    drawn_pushed: usize,
+    /// Samples held by the mesh ring
+    mesh_capacity: usize,
+    /// Samples before this global index are decimated
+    decimated_until: usize,
+    /// Pixel size the decimated samples were simplified for
+    decimated_pixel_size: f32,
+    /// World size of a screen pixel at the ribbon, from `update_ribbon_lod`
+    pixel_size: f32,
+    /// The drawn samples changed beyond the head, the whole mesh has to be rewritten
+    needs_rebuild: bool,
+    /// Simulated time of the last sampling
+    now: f32,
+    /// Minimum and maximum of `scalars`, kept up to date as samples come and go
+    scalar_bounds: Option<(f32, f32)>,
+    /// Params the curves and colormap in `base_settings` were baked from
+    baked_params: Option<MeshRibbonParams>,
+    base_settings: RibbonSettings,
+    /// Settings last written to the material
+    written_settings: Option<RibbonSettings>,
+    /// Samples changed since the mesh was last written
+    mesh_dirty: bool,
+}
+
+impl MeshRibbon {
+    /// Ribbon with a fresh ring mesh sized from `params`
+    fn new(params: MeshRibbonParams, meshes: &mut Assets<Mesh>, material_handle: RibbonMaterialHandle) -> Self {
+        // The spline looks two samples back and one ahead
+        let capacity = params.max_points.max(4);
+        let mesh_capacity = params.decimation.map_or(capacity, |d| d.max_drawn.clamp(4, capacity));
+        let subdivisions = params.subdivisions.max(1);
+        Self {
+            positions: VecDeque::with_capacity(capacity),
+            scalars: VecDeque::with_capacity(capacity),
+            times: VecDeque::with_capacity(capacity),
+            mesh_handle: meshes.add(create_empty_ribbon_mesh(mesh_capacity * subdivisions)),
+            material_handle,
+            current_position: Vec3::ZERO,
+            current_scalar: 0.0,
+            arc_length: 0.0,
+            arc_lengths: VecDeque::with_capacity(capacity),
+            pushed: 0,
+            capacity,
+            subdivisions,
+            drawn: VecDeque::with_capacity(mesh_capacity),
+            drawn_pushed: 0,
+            mesh_capacity,
+            decimated_until: 0,
+            decimated_pixel_size: 1.0,
+            pixel_size: 1.0,
+            needs_rebuild: false,
+            now: 0.0,
+            scalar_bounds: None,
+            baked_params: None,
+            base_settings: RibbonSettings::from_params(&params),
+            written_settings: None,
+            mesh_dirty: false,
+            params,
+        }
+    }
+
+    /// Whether the ribbon faces the camera in 3D rather than lying flat in the XY plane
+    pub fn is_billboard(&self) -> bool {
+        matches!(self.material_handle, RibbonMaterialHandle::Billboard(_))
+    }
+
+    /// Number of samples held by the ring
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of samples currently drawn by the mesh
+    pub fn drawn_samples(&self) -> usize {
+        self.drawn.len()
+    }
+
+    /// Number of points in the mesh ring (two vertices and one segment each)
+    fn num_points(&self) -> usize {
+        self.mesh_capacity * self.subdivisions
+    }
+
+    /// Index in `positions` of the sample with global index `g`, if it is still held
+    fn sample_index(&self, g: usize) -> Option<usize> {
+        let first = self.pushed - self.positions.len();
+        (g >= first && g < self.pushed).then(|| g - first)
+    }
+
+    /// Simulated time of the last sampling, the age reference of the shader
+    pub fn head(&self) -> f32 {
+        self.now
+    }
+
+    /// Scalar range mapped onto the colormap, `ScalarRange::Auto` spans the stored scalars
+    pub fn scalar_range(&self) -> (f32, f32) {
+        match self.params.scalar_range {
+            ScalarRange::Fixed(min, max) => (min, max),
+            ScalarRange::Auto => match self.scalar_bounds {
+                Some((min, max)) if min.is_finite() && max.is_finite() => {
+                    if (max - min).abs() < f32::EPSILON { (min - 0.5, max + 0.5) } else { (min, max) }
+                }
+                _ => (0.0, 1.0),
+            },
+        }
+    }
+
+    /// Scan the stored scalars again, after an extreme one was dropped or overwritten
+    fn rescan_scalar_bounds(&mut self) {
+        self.scalar_bounds = self.scalars.iter().fold(None, |bounds, &v| match bounds {
+            None => Some((v, v)),
+            Some((min, max)) => Some((f32::min(min, v), f32::max(max, v))),
+        });
+    }
+
+    /// Account for a stored scalar going from `old` (if any) to `new` (if any)
+    fn update_scalar_bounds(&mut self, old: Option<f32>, new: Option<f32>) {
+        let Some((min, max)) = self.scalar_bounds else {
+            return self.rescan_scalar_bounds();
+        };
+        if old.is_some_and(|old| old <= min || old >= max) {
+            return self.rescan_scalar_bounds();
+        }
+        if let Some(new) = new {
+            self.scalar_bounds = Some((min.min(new), max.max(new)));
+        }
+    }
+
+    /// Material uniforms for the current params and head of the ribbon
+    pub fn settings(&self) -> RibbonSettings {
+        self.settings_from(RibbonSettings::from_params(&self.params))
+    }
+
+    /// Same as `settings`, baking the curves and colormap again only when the params changed
+    fn refresh_settings(&mut self) -> RibbonSettings {
+        if self.baked_params.as_ref() != Some(&self.params) {
+            self.base_settings = RibbonSettings::from_params(&self.params);
+            self.baked_params = Some(self.params.clone());
+        }
+        self.settings_from(self.base_settings)
+    }
+
+    fn settings_from(&self, base: RibbonSettings) -> RibbonSettings {
+        // Only scalar gradients read the range
+        let (scalar_min, scalar_max) = match self.params.gradient_axis {
+            GradientAxis::Scalar => self.scalar_range(),
+            _ => (base.scalar_min, base.scalar_max),
+        };
+        RibbonSettings {
+            scalar_min,
+            scalar_max,
+            head: self.head(),
+            head_arc_length: self.arc_length,
+            ..base
+        }
+    }
+
+    /// Sample `position` at simulated time `time`: the head is either kept and a new one
+    /// started, or moved to `position`, following `params.spacing`
+    pub fn sample(&mut self, position: Vec3, time: f32) {
+        self.now = time;
+        if self.head_settled(position) {
+            self.push_position(position, time);
+        } else {
+            self.move_head(position, time);
+        }
+    }
+
+    /// Whether the head has gone far enough from the previous sample to be kept
+    fn head_settled(&self, position: Vec3) -> bool {
+        let len = self.positions.len();
+        let Some(&head) = self.positions.back() else {
+            return true;
+        };
+        if head.distance(position) < MIN_SAMPLE_DISTANCE {
+            return false;
+        }
+        if len < 2 {
+            return true;
+        }
+        let anchor = self.positions[len - 2];
+        match self.params.spacing {
+            SampleSpacing::EveryStep => true,
+            SampleSpacing::Interval(interval) => self.times[len - 1] - self.times[len - 2] >= interval,
+            SampleSpacing::ArcLength(length) => anchor.distance(head) >= length,
+            SampleSpacing::Curvature { max_angle, max_length } => {
+                let segment = head - anchor;
+                segment.length() >= max_length
+                    || (segment.length() >= MIN_SAMPLE_DISTANCE
+                        && segment.angle_between(position - head) >= max_angle)
+            }
+        }
+    }
+
+    /// Record a new sample, dropping the oldest one once the ring is full
+    pub fn push_position(&mut self, position: Vec3, time: f32) {
+        if let Some(last) = self.positions.back() {
+            self.arc_length += last.distance(position);
+        }
+        self.positions.push_back(position);
+        self.scalars.push_back(self.current_scalar);
+        self.times.push_back(time);
+        self.arc_lengths.push_back(self.arc_length);
+        let mut dropped_scalar = None;
+        if self.positions.len() > self.capacity {
+            self.positions.pop_front();
+            dropped_scalar = self.scalars.pop_front();
+            self.times.pop_front();
+            self.arc_lengths.pop_front();
+        }
+        self.update_scalar_bounds(dropped_scalar, Some(self.current_scalar));
+        self.pushed += 1;
+        self.mesh_dirty = true;
+
+        self.drawn.push_back(self.pushed - 1);
+        self.drawn_pushed += 1;
+        let first = self.pushed - self.positions.len();
+        while self.drawn.len() > self.mesh_capacity || self.drawn.front().is_some_and(|&g| g < first) {
+            self.drawn.pop_front();
+        }
+        self.decimate();
+    }
+
+    /// Move the newest sample, the ring keeps its size
+    pub fn move_head(&mut self, position: Vec3, time: f32) {
+        let len = self.positions.len();
+        if len == 0 {
+            self.push_position(position, time);
+            return;
+        }
+        let old_scalar = self.scalars[len - 1];
+        if self.positions[len - 1] == position && old_scalar == self.current_scalar && self.times[len - 1] == time {
+            return;
+        }
+        if len >= 2 {
+            let previous = self.positions[len - 2];
+            self.arc_length += previous.distance(position) - previous.distance(self.positions[len - 1]);
+        }
+        self.positions[len - 1] = position;
+        self.scalars[len - 1] = self.current_scalar;
+        self.times[len - 1] = time;
+        self.arc_lengths[len - 1] = self.arc_length;
+        if old_scalar != self.current_scalar {
+            self.update_scalar_bounds(Some(old_scalar), Some(self.current_scalar));
+        }
+        self.mesh_dirty = true;
+    }
+
+    /// Simplify the samples that left the recent window, once a chunk of them is ready,
+    /// or the whole history when the pixel size changed by more than `LOD_RATIO`
+    fn decimate(&mut self) {
+        let Some(decimation) = self.params.decimation else {
+            return;
+        };
+        let recent = decimation.recent.max(2);
+        let Some(end) = self.pushed.checked_sub(recent + 1) else {
+            return;
+        };
+        let first = self.pushed - self.positions.len();
+        let zoom = (self.pixel_size / self.decimated_pixel_size).max(self.decimated_pixel_size / self.pixel_size);
+        let relod = zoom > LOD_RATIO;
+        if !relod && end < self.decimated_until + recent {
+            return;
+        }
+        // A chunk starts at the last sample kept by the previous one
+        let start = if relod { first } else { self.decimated_until.saturating_sub(1).max(first) };
+        if end <= start + 1 {
+            return;
+        }
+
+        let (from, to) = (start - first, end - first);
+        let points: Vec<Vec3> = self.positions.range(from..=to).copied().collect();
+        let kept = ramer_douglas_peucker(&points, decimation.tolerance * self.pixel_size);
+
+        let mut drawn: VecDeque<usize> = self.drawn.iter().copied().filter(|&g| g >= first && g < start).collect();
+        drawn.extend(kept.into_iter().map(|i| start + i));
+        drawn.extend(end + 1..self.pushed);
+        while drawn.len() > self.mesh_capacity {
+            drawn.pop_front();
+        }
+        self.drawn_pushed = drawn.len();
+        self.drawn = drawn;
+        self.decimated_until = end + 1;
+        self.decimated_pixel_size = self.pixel_size;
+        self.needs_rebuild = true;
+    }
+
+    /// Bounding box of a subset of the samples, cheap enough to compute every frame
+    fn approximate_bounds(&self) -> Option<(Vec3, Vec3)> {
+        let step = (self.positions.len() / 256).max(1);
+        self.positions
+            .iter()
+            .step_by(step)
+            .chain(self.positions.back())
+            .fold(None, |bounds, &p| match bounds {
+                None => Some((p, p)),
+                Some((min, max)) => Some((min.min(p), max.max(p))),
+            })
+    }
+
+    /// Points of the drawn samples from index `first` in `drawn` up to the head, each drawn
+    /// sample giving `subdivisions` points on the Catmull–Rom spline from the previous one
+    pub(crate) fn resample_drawn(&self, first: usize) -> Vec<RibbonPoint> {
+        let subdivisions = self.subdivisions;
+        let len = self.drawn.len();
+
+        // Index in the sample deques of drawn sample `j`, drawn samples are always still held
+        let index = |j: usize| self.sample_index(self.drawn[j]).expect("drawn sample was dropped");
+        let position = |j: usize| self.positions[index(j)];
+        let lerp = |values: &VecDeque<f32>, j: usize, t: f32| {
+            let (a, b) = (values[index(j - 1)], values[index(j)]);
+            a + (b - a) * t
+        };
+
+        let mut points = Vec::with_capacity(len.saturating_sub(first) * subdivisions);
+        for j in first..len {
+            if j == 0 {
+                let i = index(0);
+                points.extend((0..subdivisions).map(|_| RibbonPoint {
+                    position: self.positions[i],
+                    birth: self.times[i],
+                    arc_length: self.arc_lengths[i],
+                    scalar: self.scalars[i],
+                }));
+                continue;
+            }
+            let p1 = position(j - 1);
+            let p2 = position(j);
+            // Missing neighbours are mirrored, giving a straight end tangent
+            let p0 = if j >= 2 { position(j - 2) } else { 2.0 * p1 - p2 };
+            let p3 = if j + 1 < len { position(j + 1) } else { 2.0 * p2 - p1 };
+            for i in 1..=subdivisions {
+                let t = i as f32 / subdivisions as f32;
+                points.push(RibbonPoint {
+                    position: if subdivisions == 1 { p2 } else { catmull_rom(p0, p1, p2, p3, t) },
+                    birth: lerp(&self.times, j, t),
+                    arc_length: lerp(&self.arc_lengths, j, t),
+                    scalar: lerp(&self.scalars, j, t),
+                });
+            }
+        }
+        points
+    }
+}
+
+/// Entity followed by a ribbon: each frame `follow_ribbon_targets` copies the target's
+/// `GlobalTransform` translation into `MeshRibbon::current_position`.
+/// The ribbon itself should stay at the world origin, since its mesh is in world space.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RibbonTarget(pub Entity);
+
+/// Optional component on a followed entity, providing the scalar recorded with
+/// each sample of the ribbons following it (for `GradientAxis::Scalar`)
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct TrailSource {
+    pub scalar: f32,
+}
+
+/// Simulated time ribbons are sampled and aged with, advanced every fixed step by the
+/// fixed timestep times `time_scale` (the simulated seconds per second of the app)
+#[derive(Resource, Clone, Debug)]
+pub struct RibbonClock {
+    pub time_scale: f32,
+    elapsed: f32,
+}
+
+impl RibbonClock {
+    pub fn new(time_scale: f32) -> Self {
+        Self { time_scale, elapsed: 0.0 }
+    }
+
+    /// Simulated seconds since startup
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+}
+
+impl Default for RibbonClock {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
 }
 
 /// Spawns a mesh-based ribbon entity
 pub fn spawn_mesh_ribbon(
     commands: &mut Commands,
     meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<ColorMaterial>,
+    materials: &mut Assets<RibbonMaterial>,
     name: String,
     params: MeshRibbonParams,
 ) -> Entity {
-    let mesh = create_empty_ribbon_mesh();
-    let mesh_handle = meshes.add(mesh);
-    
-    let material = materials.add(ColorMaterial {
-        color: params.color,
-        ..default()
+    let material_handle = materials.add(RibbonMaterial {
+        settings: RibbonSettings::from_params(&params),
     });
 
+    let ribbon = MeshRibbon::new(params, meshes, RibbonMaterialHandle::Flat(material_handle.clone()));
+
     commands.spawn((
-        MeshRibbon {
-            params: params.clone(),
-            positions: VecDeque::with_capacity(params.max_points),
-            mesh_handle: mesh_handle.clone(),
-            current_position: Vec3::ZERO,
-        },
-        Mesh2d(mesh_handle),
-        MeshMaterial2d(material),
+        Mesh2d(ribbon.mesh_handle.clone()),
+        ribbon,
+        MeshMaterial2d(material_handle),
         Transform::from_translation(Vec3::ZERO),
+        // The mesh bounds change every frame
+        NoFrustumCulling,
         Name::new(name),
     ))
     .id()
 }
 
-/// Creates an empty ribbon mesh
-fn create_empty_ribbon_mesh() -> Mesh {
+/// Spawns a camera-facing 3D ribbon entity, positions are used as is in world space
+pub fn spawn_mesh_ribbon_3d(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<RibbonMaterial3d>,
+    name: String,
+    params: MeshRibbonParams,
+) -> Entity {
+    let material_handle = materials.add(RibbonMaterial3d {
+        settings: RibbonSettings::from_params(&params),
+    });
+
+    let ribbon = MeshRibbon::new(params, meshes, RibbonMaterialHandle::Billboard(material_handle.clone()));
+
+    commands.spawn((
+        Mesh3d(ribbon.mesh_handle.clone()),
+        ribbon,
+        MeshMaterial3d(material_handle),
+        Transform::from_translation(Vec3::ZERO),
+        NoFrustumCulling,
+        Name::new(name),
+    ))
+    .id()
+}
+
+/// Creates a ribbon mesh with `num_points` ring slots, all degenerate until written
+fn create_empty_ribbon_mesh(num_points: usize) -> Mesh {
+    let vertex_count = num_points * 2;
     Mesh::new(
         PrimitiveTopology::TriangleList,
         RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
     )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; vertex_count])
+    .with_inserted_attribute(ATTRIBUTE_RIBBON_SIDE, vec![[0.0f32; 4]; vertex_count])
+    .with_inserted_attribute(ATTRIBUTE_RIBBON_BIRTH, vec![UNWRITTEN_BIRTH; vertex_count])
+    .with_inserted_attribute(ATTRIBUTE_RIBBON_ARC_LENGTH, vec![0.0f32; vertex_count])
+    .with_inserted_attribute(ATTRIBUTE_RIBBON_SCALAR, vec![0.0f32; vertex_count])
+    .with_inserted_indices(Indices::U32(vec![0; num_points * 6]))
+}
+
+/// Point at `t` in [0, 1] on the centripetal Catmull–Rom segment from `p1` to `p2`.
+/// The centripetal parametrization never overshoots into loops or cusps within a segment.
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let knot = |a: Vec3, b: Vec3| a.distance(b).sqrt().max(1e-4);
+    let t1 = knot(p0, p1);
+    let t2 = t1 + knot(p1, p2);
+    let t3 = t2 + knot(p2, p3);
+    let u = t1 + (t2 - t1) * t;
+
+    let a1 = p0 * ((t1 - u) / t1) + p1 * (u / t1);
+    let a2 = p1 * ((t2 - u) / (t2 - t1)) + p2 * ((u - t1) / (t2 - t1));
+    let a3 = p2 * ((t3 - u) / (t3 - t2)) + p3 * ((u - t2) / (t3 - t2));
+    let b1 = a1 * ((t2 - u) / t2) + a2 * (u / t2);
+    let b2 = a2 * ((t3 - u) / (t3 - t1)) + a3 * ((u - t1) / (t3 - t1));
+    b1 * ((t2 - u) / (t2 - t1)) + b2 * ((u - t1) / (t2 - t1))
+}
+
+/// Side vector of a point given its neighbours on the ribbon.
+/// 2D ribbons get the join offset (perpendicular bisector scaled by the join style), 3D ribbons
+/// the averaged tangent. `fallback` is used when the neighbours give no direction.
+fn join_side(previous: Option<Vec3>, point: Vec3, next: Option<Vec3>, join: JoinStyle, billboard: bool, fallback: Vec3) -> Vec3 {
+    let incoming = previous.map_or(Vec3::ZERO, |p| (point - p).normalize_or_zero());
+    let outgoing = next.map_or(Vec3::ZERO, |n| (n - point).normalize_or_zero());
+
+    if billboard {
+        return (incoming + outgoing).try_normalize()
+            .or_else(|| incoming.try_normalize())
+            .unwrap_or(fallback);
+    }
+
+    let perpendicular = |t: Vec3| Vec3::new(-t.y, t.x, 0.0).try_normalize();
+    match (perpendicular(incoming), perpendicular(outgoing)) {
+        (None, None) => fallback,
+        (Some(n), None) | (None, Some(n)) => n,
+        (Some(n_in), Some(n_out)) => {
+            // Cusps and reversals have no meaningful bisector, keep the incoming side
+            let Some(bisector) = (n_in + n_out).try_normalize() else {
+                return n_in;
+            };
+            if incoming.dot(outgoing) < -0.999 {
+                return n_in;
+            }
+            match join {
+                JoinStyle::Miter { limit } => {
+                    let stretch = 1.0 / bisector.dot(n_in).max(1e-3);
+                    bisector * stretch.min(limit.max(1.0))
+                }
+                JoinStyle::Round => bisector,
+            }
+        }
+    }
 }
 
-/// Updates the ribbon mesh based on its position history
+/// One resampled point of the ribbon
+#[derive(Clone, Copy)]
+pub(crate) struct RibbonPoint {
+    pub position: Vec3,
+    pub birth: f32,
+    pub arc_length: f32,
+    pub scalar: f32,
+}
+
+/// Writes the newest sample into the ring buffer mesh, or the whole mesh after decimation.
+/// Every drawn sample owns `subdivisions` points resampled on the Catmull–Rom spline from the
+/// previous drawn sample. The newest segment is first drawn with an extrapolated end tangent and
+/// rewritten as the head moves and once the next sample is known, so only the last two
+/// segments, the side of the point before them and the segments around the ring seam are touched.
+///
+/// Only the CPU write is incremental: Bevy extracts and uploads a changed mesh asset whole, at
+/// 104 bytes per ring point (two 40 byte vertices and six u32 indices), at most once per frame.
+/// That is 3.1 MB for 30k points (Lorenz example), whose repacking alone measured 1.1 ms on a
+/// desktop release build (0.15 ms for 1000 samples × 4 subdivisions), the GPU copy comes on top.
+/// Nothing is written while no sample changes, e.g. with the ribbon clock paused.
 pub fn update_ribbon_mesh(
-    ribbon: &MeshRibbon,
+    ribbon: &mut MeshRibbon,
     meshes: &mut Assets<Mesh>,
 ) {
-    let positions = &ribbon.positions;
-    if positions.len() < 2 {
+    // Touching the mesh asset re-uploads it, skip it when no sample changed
+    let dirty = std::mem::take(&mut ribbon.mesh_dirty) || ribbon.needs_rebuild;
+    if ribbon.drawn.is_empty() || !dirty {
         return;
     }
+    let Some(mesh) = meshes.get_mut(&ribbon.mesh_handle) else {
+        return;
+    };
+    let first = if std::mem::take(&mut ribbon.needs_rebuild) {
+        clear_ribbon_mesh(mesh);
+        0
+    } else {
+        // The segment ending at the previous sample can now use the true next sample
+        ribbon.drawn.len().saturating_sub(2)
+    };
+    write_drawn_samples(ribbon, mesh, first);
+}
 
-    let width = ribbon.params.width;
-    let mut vertices = Vec::new();
-    let mut normals = Vec::new();
-    let mut colors = Vec::new();
-    let mut indices = Vec::new();
-
-    // Generate vertices along the ribbon
-    for (i, pos) in positions.iter().enumerate() {
-        let progress = i as f32 / (positions.len() - 1) as f32;
-        
-        // Calculate perpendicular direction (in 2D, perpendicular to direction of motion)
-        let tangent = if i < positions.len() - 1 {
-            (positions[i + 1] - *pos).normalize_or_zero()
-        } else if i > 0 {
-            (*pos - positions[i - 1]).normalize_or_zero()
-        } else {
-            Vec3::X
-        };
+/// Marks every ring point as unwritten and removes all segments
+fn clear_ribbon_mesh(mesh: &mut Mesh) {
+    if let Some(VertexAttributeValues::Float32(births)) = mesh.attribute_mut(ATTRIBUTE_RIBBON_BIRTH) {
+        births.fill(UNWRITTEN_BIRTH);
+    }
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        indices.fill(0);
+    }
+}
 
-        // Get perpendicular vector (cross with up vector for 2D ribbons in XY plane)
-        let perpendicular = Vec3::new(-tangent.y, tangent.x, 0.0).normalize_or_zero();
-        if perpendicular.length_squared() < 0.01 {
-            continue;
-        }
+/// Writes the drawn samples from index `first` in `MeshRibbon::drawn` up to the head
+fn write_drawn_samples(ribbon: &MeshRibbon, mesh: &mut Mesh, first: usize) {
+    let subdivisions = ribbon.subdivisions;
+    let num_points = ribbon.num_points();
+    let len = ribbon.drawn.len();
+    let points = ribbon.resample_drawn(first);
 
-        let half_width = width * 0.5;
+    let first_point = (ribbon.drawn_pushed - len + first) * subdivisions;
+    let slot = |m: usize| m % num_points;
 
-        let width_progress = match &ribbon.params.width_variation {
-            InterpolationType::Poly(power) => progress.powf(*power),
-        };
+    // The two points before the rewritten ones, already in the mesh
+    let (before, before_previous) = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(vertices)) => (
+            (first_point >= 1).then(|| Vec3::from(vertices[slot(first_point - 1) * 2])),
+            (first_point >= 2).then(|| Vec3::from(vertices[slot(first_point - 2) * 2])),
+        ),
+        _ => (None, None),
+    };
 
-        let transparency_progress = match &ribbon.params.transparency_variance {
-            InterpolationType::Poly(power) => progress.powf(*power),
-        };
+    let join = ribbon.params.join;
+    let billboard = ribbon.is_billboard();
+    let default_side = if billboard { Vec3::X } else { Vec3::Y };
 
-        let left = *pos + perpendicular * half_width * width_progress;
-        let right = *pos - perpendicular * half_width * width_progress;
+    if let Some(VertexAttributeValues::Float32x3(vertices)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+        for (i, point) in points.iter().enumerate() {
+            let s = slot(first_point + i);
+            vertices[s * 2] = point.position.to_array();
+            vertices[s * 2 + 1] = point.position.to_array();
+        }
+    }
 
-        vertices.push([left.x, left.y, left.z]);
-        vertices.push([right.x, right.y, right.z]);
+    if let Some(VertexAttributeValues::Float32x4(sides)) = mesh.attribute_mut(ATTRIBUTE_RIBBON_SIDE) {
+        let mut write_side = |m: usize, side: Vec3| {
+            sides[slot(m) * 2] = side.extend(1.0).to_array();
+            sides[slot(m) * 2 + 1] = side.extend(-1.0).to_array();
+        };
+        let mut last_side = default_side;
+        if let Some(before) = before {
+            let next = points.first().map(|p| p.position);
+            last_side = join_side(before_previous, before, next, join, billboard, default_side);
+            write_side(first_point - 1, last_side);
+        }
+        for i in 0..points.len() {
+            let previous = if i > 0 { Some(points[i - 1].position) } else { before };
+            let next = points.get(i + 1).map(|p| p.position);
+            last_side = join_side(previous, points[i].position, next, join, billboard, last_side);
+            write_side(first_point + i, last_side);
+        }
+    }
 
-        // Normals pointing toward camera (for 2D)
-        normals.push([0.0, 0.0, 1.0]);
-        normals.push([0.0, 0.0, 1.0]);
+    if let Some(VertexAttributeValues::Float32(births)) = mesh.attribute_mut(ATTRIBUTE_RIBBON_BIRTH) {
+        for (i, point) in points.iter().enumerate() {
+            let s = slot(first_point + i);
+            births[s * 2] = point.birth;
+            births[s * 2 + 1] = point.birth;
+        }
+    }
 
-        // Colors with fade
-        let alpha = if ribbon.params.fade_to_transparent {
-            transparency_progress / 4.0
-        } else {
-            1.0 / 4.0
-        };
-        colors.push([1.0, 1.0, 1.0, alpha]);
-        colors.push([1.0, 1.0, 1.0, alpha]);
+    if let Some(VertexAttributeValues::Float32(arc_lengths)) = mesh.attribute_mut(ATTRIBUTE_RIBBON_ARC_LENGTH) {
+        for (i, point) in points.iter().enumerate() {
+            let s = slot(first_point + i);
+            arc_lengths[s * 2] = point.arc_length;
+            arc_lengths[s * 2 + 1] = point.arc_length;
+        }
     }
 
-    // Generate indices for triangles
-    for i in 0..(positions.len() - 1) {
-        let base = (i * 2) as u32;
-        // First triangle
-        indices.push(base);
-        indices.push(base + 2);
-        indices.push(base + 1);
-        // Second triangle
-        indices.push(base + 1);
-        indices.push(base + 2);
-        indices.push(base + 3);
+    if let Some(VertexAttributeValues::Float32(scalars)) = mesh.attribute_mut(ATTRIBUTE_RIBBON_SCALAR) {
+        for (i, point) in points.iter().enumerate() {
+            let s = slot(first_point + i);
+            scalars[s * 2] = point.scalar;
+            scalars[s * 2 + 1] = point.scalar;
+        }
     }
 
-    // Update the mesh
-    if let Some(mesh) = meshes.get_mut(&ribbon.mesh_handle) {
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
-        mesh.insert_indices(Indices::U32(indices));
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        // Segments joining each written point to the one before it
+        for m in first_point.max(1)..first_point + points.len() {
+            let (from, to) = (slot(m - 1), slot(m));
+            let base = (from * 2) as u32;
+            let next = (to * 2) as u32;
+            indices[from * 6..from * 6 + 6].copy_from_slice(&[base, next, base + 1, base + 1, next, next + 1]);
+        }
+        // The newest point overwrote the oldest one, cut the segment leading to the next oldest
+        let head = slot(first_point + points.len() - 1);
+        indices[head * 6..head * 6 + 6].fill(0);
     }
 }
 
-/// System to add new positions to ribbons
+/// System advancing `RibbonClock` by one fixed step
+pub fn tick_ribbon_clock(time_fixed: Res<Time<Fixed>>, mut clock: ResMut<RibbonClock>) {
+    clock.elapsed += time_fixed.delta_secs() * clock.time_scale;
+}
+
+/// System to sample ribbons at their current position, once per fixed step.
+/// The material is refreshed from `MeshRibbon::params` at the same time, so width, fade,
+/// colormap and glow can be changed at runtime. `max_points` is fixed at spawn.
+/// The curves are baked again only when the params change, and the material is only touched
+/// when its uniforms differ from the last ones written (the head time moves while the clock runs).
 pub fn add_ribbon_position(
     mut query: Query<&mut MeshRibbon>,
     mut meshes: ResMut<Assets<Mesh>>,
-    time_fixed: Res<Time<Fixed>>
+    // Either material may be missing when the app only draws 2D or 3D ribbons
+    mut materials: Option<ResMut<Assets<RibbonMaterial>>>,
+    mut materials_3d: Option<ResMut<Assets<RibbonMaterial3d>>>,
+    clock: Res<RibbonClock>,
 ) {
-    if time_fixed.elapsed_secs() < 0.1 {
-        return;
-    }
     for mut ribbon in query.iter_mut() {
         let new_pos = ribbon.current_position;
-        
-        // Only add if position changed significantly
-        if let Some(last_pos) = ribbon.positions.back() {
-            if last_pos.distance(new_pos) < 0.001 {
-                continue;
+        ribbon.sample(new_pos, clock.elapsed());
+        update_ribbon_mesh(&mut ribbon, &mut meshes);
+
+        let settings = ribbon.refresh_settings();
+        if ribbon.written_settings == Some(settings) {
+            continue;
+        }
+        ribbon.written_settings = Some(settings);
+        match &ribbon.material_handle {
+            RibbonMaterialHandle::Flat(handle) => {
+                if let Some(material) = materials.as_mut().and_then(|m| m.get_mut(handle)) {
+                    material.settings = settings;
+                }
+            }
+            RibbonMaterialHandle::Billboard(handle) => {
+                if let Some(material) = materials_3d.as_mut().and_then(|m| m.get_mut(handle)) {
+                    material.settings = settings;
+                }
             }
         }
+    }
+}
 
-        ribbon.positions.push_back(new_pos);
-        
-        // Remove old positions
-        if ribbon.positions.len() > ribbon.params.max_points {
-            ribbon.positions.pop_front();
+/// System measuring the world size of a screen pixel at each decimated ribbon, from the first
+/// active camera of the matching kind: orthographic for flat ribbons, perspective for 3D ones
+/// (at the nearest point of the ribbon's bounds)
+pub fn update_ribbon_lod(
+    mut query: Query<&mut MeshRibbon>,
+    q_camera: Query<(&Camera, &GlobalTransform, &Projection)>,
+) {
+    for mut ribbon in query.iter_mut() {
+        if ribbon.params.decimation.is_none() {
+            continue;
         }
+        let billboard = ribbon.is_billboard();
+        let pixel_size = q_camera
+            .iter()
+            .filter(|(camera, ..)| camera.is_active)
+            .find_map(|(camera, transform, projection)| {
+                let height = camera.logical_viewport_size()?.y;
+                match (projection, billboard) {
+                    (Projection::Orthographic(orthographic), false) => Some(orthographic.area.height() / height),
+                    (Projection::Perspective(perspective), true) => {
+                        let eye = transform.translation();
+                        let (min, max) = ribbon.approximate_bounds()?;
+                        let distance = eye.distance(eye.clamp(min, max)).max(perspective.near);
+                        Some(2.0 * distance * (perspective.fov / 2.0).tan() / height)
+                    }
+                    _ => None,
+                }
+            });
+        if let Some(pixel_size) = pixel_size.filter(|size| size.is_finite() && *size > 0.0) {
+            ribbon.pixel_size = pixel_size;
+        }
+    }
+}
+
+/// System to move ribbons onto the entity they follow.
+/// The target's global transform is computed from its hierarchy, so targets moved earlier
+/// in the same fixed step are followed even though propagation only runs once per frame.
+pub fn follow_ribbon_targets(
+    mut query: Query<(&mut MeshRibbon, &RibbonTarget)>,
+    q_source: Query<&TrailSource>,
+    transforms: TransformHelper,
+) {
+    for (mut ribbon, target) in query.iter_mut() {
+        let Ok(transform) = transforms.compute_global_transform(target.0) else {
+            continue;
+        };
+        ribbon.current_position = transform.translation();
+        if let Ok(source) = q_source.get(target.0) {
+            ribbon.current_scalar = source.scalar;
+        }
+    }
+}
+
+/// Registers the ribbon materials, the ribbon clock and the systems sampling ribbons and
+/// their color bars. Sampling runs in `FixedPostUpdate`, after the simulation stepped and
+/// moved the targets in `FixedUpdate`, so ribbons look the same at any framerate.
+pub struct MeshRibbonPlugin;
+
+impl Plugin for MeshRibbonPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/ribbon.wgsl");
+        app.add_plugins(Material2dPlugin::<RibbonMaterial>::default())
+            .add_plugins(MaterialPlugin::<RibbonMaterial3d> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..default()
+            })
+            .init_resource::<RibbonClock>()
+            .add_systems(
+                FixedPostUpdate,
+                (tick_ribbon_clock, follow_ribbon_targets, add_ribbon_position).chain(),
+            )
+            .add_systems(Update, (update_ribbon_lod, update_colorbar));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(params: &MeshRibbonParams) -> RibbonSettings {
+        RibbonSettings { head: 10.0, head_arc_length: 50.0, ..RibbonSettings::from_params(params) }
+    }
+
+    #[test]
+    fn lookup_table_hits_the_curves_at_its_nodes() {
+        let params = MeshRibbonParams::default();
+        let settings = settings(&params);
+        for i in 0..CURVE_LUT_SIZE {
+            let x = i as f32 / (CURVE_LUT_SIZE - 1) as f32;
+            let curves = settings.lookup_curves(x);
+            assert!((curves.x - params.width_variation.evaluate(x)).abs() < 1e-6, "width at {}", x);
+            assert!((curves.y - params.transparency_variance.evaluate(x)).abs() < 1e-6, "alpha at {}", x);
+            assert!((curves.z - params.color_variation.evaluate(x)).abs() < 1e-6, "color at {}", x);
+        }
+    }
+
+    #[test]
+    fn vertex_style_follows_the_ribbon_params() {
+        let params = MeshRibbonParams {
+            colormap: Colormap::viridis(),
+            fade_to_transparent: true,
+            ..default()
+        };
+        let settings = settings(&params);
+        for i in 0..=100 {
+            let birth = settings.head - settings.max_age * i as f32 / 100.0;
+            let progress = 1.0 - i as f32 / 100.0;
+            let (half_width, color) = settings.vertex_style(birth, 0.0, 0.0);
+
+            let width = 0.5 * params.width * params.width_variation.evaluate(progress);
+            let alpha = params.transparency_variance.evaluate(progress) / 4.0;
+            let gradient = params.colormap.sample(1.0 - params.color_variation.evaluate(progress)).to_linear().to_vec4();
+            let tint = params.color.to_linear().to_vec4() * gradient;
+            let expected = (tint.truncate() * params.glow).extend(tint.w * alpha);
+            // The lookup table interpolates the curves linearly between its nodes
+            assert!((half_width - width).abs() < 1e-2 * params.width, "width at {}: {} vs {}", progress, half_width, width);
+            assert!((color - expected).abs().max_element() < 1e-2 * params.glow.max(1.0), "color at {}: {} vs {}", progress, color, expected);
+        }
+    }
 
-        update_ribbon_mesh(&ribbon, &mut meshes);
+    #[test]
+    fn expired_vertices_are_invisible() {
+        let params = MeshRibbonParams { fade_to_transparent: true, ..default() };
+        let settings = settings(&params);
+        let (half_width, color) = settings.vertex_style(settings.head - 2.0 * settings.max_age, 0.0, 0.0);
+        assert_eq!(half_width, 0.0);
+        assert_eq!(color.w, 0.0);
     }
 }
@@ -1,4 +1,21 @@
 pub mod ODEs;
 pub mod rk4;
+pub mod colormap;
+pub mod colorbar;
+pub mod interpolation;
+pub mod decimation;
+pub mod figure;
 pub mod mesh_ribbon;
-pub mod graph;
\ No newline at end of file
+pub mod orbit_camera;
+pub mod graph;
+pub mod graph_overlay;
+pub mod graph_interaction;
+pub mod graph_export;
+pub mod spectrum;
+pub mod histogram;
+pub mod probe;
+pub mod recurrence;
+pub mod invariants;
+pub mod integrator;
+pub mod simulation;
+pub mod export;
\ No newline at end of file
@@ -0,0 +1,75 @@
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+/// Camera rotating around `target` on a sphere of radius `radius`, Y is up.
+/// Drag with the left mouse button to rotate and scroll to zoom.
+#[derive(Component, Clone, Debug)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub radius: f32,
+    /// Rotation around the Y axis (radians)
+    pub yaw: f32,
+    /// Elevation above the XZ plane (radians), kept away from the poles
+    pub pitch: f32,
+    /// Radians per pixel of mouse motion
+    pub rotate_sensitivity: f32,
+    /// Relative radius change per scroll line
+    pub zoom_sensitivity: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            radius: 10.0,
+            yaw: 0.0,
+            pitch: 0.3,
+            rotate_sensitivity: 0.005,
+            zoom_sensitivity: 0.1,
+            min_radius: 0.1,
+            max_radius: 1.0e5,
+        }
+    }
+}
+
+impl OrbitCamera {
+    /// Camera transform looking at `target` from the current yaw, pitch and radius
+    pub fn transform(&self) -> Transform {
+        let offset = Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        ) * self.radius;
+        Transform::from_translation(self.target + offset).looking_at(self.target, Vec3::Y)
+    }
+}
+
+/// System rotating and zooming `OrbitCamera`s from the mouse
+pub fn orbit_camera(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    mut q_camera: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    // Pixel scrolling (touchpads) is brought back to roughly one line per notch
+    let scroll_lines = match mouse_scroll.unit {
+        MouseScrollUnit::Line => mouse_scroll.delta.y,
+        MouseScrollUnit::Pixel => mouse_scroll.delta.y / 16.0,
+    };
+
+    for (mut orbit, mut transform) in q_camera.iter_mut() {
+        if mouse_buttons.pressed(MouseButton::Left) {
+            orbit.yaw -= mouse_motion.delta.x * orbit.rotate_sensitivity;
+            orbit.pitch = (orbit.pitch + mouse_motion.delta.y * orbit.rotate_sensitivity)
+                .clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+        }
+        if scroll_lines != 0.0 {
+            let factor = (1.0 - scroll_lines * orbit.zoom_sensitivity).max(0.1);
+            orbit.radius = (orbit.radius * factor).clamp(orbit.min_radius, orbit.max_radius);
+        }
+        *transform = orbit.transform();
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+
+use crate::utils::graph::GraphWidget;
+use crate::utils::histogram::HistogramWidget;
+use crate::utils::spectrum::SpectrumWidget;
+
+/// Widgets a probe can feed
+type ProbedWidget = AnyOf<(&'static mut GraphWidget, &'static mut SpectrumWidget, &'static mut HistogramWidget)>;
+
+/// Simulation states exposing scalar quantities by name, for `Probe::named`
+pub trait Observables: Resource {
+    /// Value of the quantity called `name`, `None` if the state has no such quantity
+    fn observable(&self, name: &str) -> Option<f32> {
+        let _ = name;
+        None
+    }
+}
+
+/// Scalar read from the simulation state `S`
+pub enum Observable<S> {
+    Fn(Box<dyn Fn(&S) -> f32 + Send + Sync>),
+    /// Looked up through `Observables::observable`
+    Named(String),
+}
+
+impl<S: Observables> Observable<S> {
+    fn eval(&self, state: &S) -> Option<f32> {
+        match self {
+            Observable::Fn(f) => Some(f(state)),
+            Observable::Named(name) => state.observable(name),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Observable::Fn(_) => "closure",
+            Observable::Named(name) => name,
+        }
+    }
+}
+
+/// Feeds the widget on the same entity from the simulation state `S` every fixed step, see `probe_graphs`.
+/// A `GraphWidget` gets one point per observable, in series order, plotted against the fixed time
+/// unless `x` is set. A `SpectrumWidget` or `HistogramWidget` gets the first observable as its sample.
+#[derive(Component)]
+pub struct Probe<S: Observables> {
+    /// Horizontal coordinate of the points, `None` for the fixed time
+    pub x: Option<Observable<S>>,
+    /// Value of each series
+    pub series: Vec<Observable<S>>,
+    /// Names already reported missing from the state
+    missing: Vec<String>,
+}
+
+impl<S: Observables> Default for Probe<S> {
+    fn default() -> Self {
+        Self {
+            x: None,
+            series: Vec::new(),
+            missing: Vec::new(),
+        }
+    }
+}
+
+impl<S: Observables> Probe<S> {
+    /// Probe plotting `f` against time, e.g. `Probe::new(|s: &PendulumState| s.theta1())`
+    pub fn new(f: impl Fn(&S) -> f32 + Send + Sync + 'static) -> Self {
+        Self::default().with(f)
+    }
+
+    /// Probe plotting the state's observable `name` against time
+    pub fn named(name: impl Into<String>) -> Self {
+        Self::default().with_named(name)
+    }
+
+    /// Add a series fed by `f`
+    pub fn with(mut self, f: impl Fn(&S) -> f32 + Send + Sync + 'static) -> Self {
+        self.series.push(Observable::Fn(Box::new(f)));
+        self
+    }
+
+    /// Add a series fed by the state's observable `name`
+    pub fn with_named(mut self, name: impl Into<String>) -> Self {
+        self.series.push(Observable::Named(name.into()));
+        self
+    }
+
+    /// Plot against `f` instead of time, for phase and state space plots
+    pub fn with_x(mut self, f: impl Fn(&S) -> f32 + Send + Sync + 'static) -> Self {
+        self.x = Some(Observable::Fn(Box::new(f)));
+        self
+    }
+
+    /// Plot against the state's observable `name` instead of time
+    pub fn with_x_named(mut self, name: impl Into<String>) -> Self {
+        self.x = Some(Observable::Named(name.into()));
+        self
+    }
+}
+
+/// Evaluate `observable` at `state`, warning once about names the state does not have
+fn eval<S: Observables>(observable: &Observable<S>, state: &S, missing: &mut Vec<String>) -> Option<f32> {
+    let value = observable.eval(state);
+    if value.is_none() && !missing.iter().any(|name| name == observable.name()) {
+        log::warn!("Probe: no observable named \"{}\" in the simulation state", observable.name());
+        missing.push(observable.name().to_string());
+    }
+    value
+}
+
+/// System evaluating every `Probe<S>` and feeding its widget, run it in `FixedUpdate` after the step
+pub fn probe_graphs<S: Observables>(
+    state: Res<S>,
+    time_fixed: Res<Time<Fixed>>,
+    mut query: Query<(&mut Probe<S>, ProbedWidget)>,
+) {
+    let time = time_fixed.elapsed_secs();
+
+    for (mut probe, (graph, spectrum, histogram)) in query.iter_mut() {
+        let Probe { x, series, missing } = &mut *probe;
+        let values: Vec<Option<f32>> = series.iter().map(|observable| eval(observable, &state, missing)).collect();
+        if let Some(mut graph) = graph {
+            let x = match x {
+                Some(observable) => eval(observable, &state, missing),
+                None => Some(time),
+            };
+            let Some(x) = x else {
+                continue;
+            };
+            for (index, value) in values.iter().enumerate() {
+                if let Some(y) = value {
+                    graph.add_series_point(index, x, *y);
+                }
+            }
+        }
+
+        let Some(&Some(value)) = values.first() else {
+            continue;
+        };
+        if let Some(mut spectrum) = spectrum {
+            spectrum.add_sample(value);
+        }
+        if let Some(mut histogram) = histogram {
+            histogram.add_sample(value);
+        }
+    }
+}
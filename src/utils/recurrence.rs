@@ -0,0 +1,370 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::sprite::Anchor;
+
+#[derive(Clone)]
+pub struct RecurrenceParams {
+    /// Position on screen (top-left corner)
+    pub position: Vec2,
+    /// Size of the plot on screen
+    pub size: Vec2,
+    /// Number of buffered states, the plot is `window x window` pixels
+    pub window: usize,
+    /// Two states recur when their euclidean distance is below this threshold
+    pub threshold: f32,
+    /// Keep one state out of every `downsample` pushed (1 keeps all of them)
+    pub downsample: usize,
+    /// Minimum length of a diagonal line counted towards determinism
+    pub min_diagonal: usize,
+    /// Refresh the texture and quantification every `update_interval` kept states
+    pub update_interval: usize,
+    /// Color of recurrent pixels
+    pub recurrence_color: Color,
+    /// Color of non-recurrent pixels
+    pub background_color: Color,
+    /// Label for the plot
+    pub label: String,
+    /// Text color
+    pub text_color: Color,
+    /// Font size for labels
+    pub font_size: f32,
+}
+
+impl Default for RecurrenceParams {
+    fn default() -> Self {
+        Self {
+            position: Vec2::new(350.0, 320.0),
+            size: Vec2::new(250.0, 250.0),
+            window: 400,
+            threshold: 0.1,
+            downsample: 1,
+            min_diagonal: 2,
+            update_interval: 10,
+            recurrence_color: Color::srgba(0.9, 0.9, 0.9, 1.0),
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6),
+            label: "Recurrence plot".to_string(),
+            text_color: Color::srgba(0.9, 0.9, 0.9, 1.0),
+            font_size: 12.0,
+        }
+    }
+}
+
+/// Recurrence plot of the last `window` state vectors pushed from a simulation
+#[derive(Component)]
+pub struct RecurrencePlot {
+    pub params: RecurrenceParams,
+    /// Ring buffer of state vectors, slot `k % window` holds the k-th kept state
+    states: Vec<Vec<f32>>,
+    /// Thresholded distance matrix between ring buffer slots (`window x window`)
+    matrix: Vec<bool>,
+    /// Number of states kept so far
+    kept: usize,
+    pushed: usize,
+    kept_since_update: usize,
+    /// Fraction of recurrent pairs (main diagonal excluded)
+    pub recurrence_rate: f32,
+    /// Fraction of recurrent pairs lying on diagonal lines of at least `min_diagonal`
+    pub determinism: f32,
+    pub image: Handle<Image>,
+    /// Text entity showing the quantification numbers
+    pub stats_text: Entity,
+}
+
+impl RecurrencePlot {
+    /// Number of states currently buffered
+    pub fn len(&self) -> usize {
+        self.kept.min(self.params.window)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kept == 0
+    }
+
+    /// Push a new state vector, only the new row/column of the matrix is computed
+    pub fn push_state(&mut self, state: &[f32]) {
+        self.pushed += 1;
+        if !(self.pushed - 1).is_multiple_of(self.params.downsample.max(1)) {
+            return;
+        }
+
+        let window = self.params.window;
+        let slot = self.kept % window;
+        self.states[slot].clear();
+        self.states[slot].extend_from_slice(state);
+        self.kept += 1;
+
+        let threshold_sq = self.params.threshold * self.params.threshold;
+        for other in 0..self.len() {
+            let distance_sq: f32 = self.states[slot]
+                .iter()
+                .zip(&self.states[other])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            let recurrent = distance_sq <= threshold_sq;
+            self.matrix[slot * window + other] = recurrent;
+            self.matrix[other * window + slot] = recurrent;
+        }
+
+        self.kept_since_update += 1;
+    }
+
+    /// Recurrence between the i-th and j-th buffered states (0 is the oldest)
+    pub fn is_recurrent(&self, i: usize, j: usize) -> bool {
+        let window = self.params.window;
+        let start = self.kept - self.len();
+        self.matrix[((start + i) % window) * window + (start + j) % window]
+    }
+
+    /// Recompute recurrence rate and determinism over the buffered states
+    pub fn quantify(&mut self) {
+        let n = self.len();
+        if n < 2 {
+            self.recurrence_rate = 0.0;
+            self.determinism = 0.0;
+            return;
+        }
+
+        // The matrix is symmetric, scan the diagonals above the main one
+        let min_diagonal = self.params.min_diagonal.max(1);
+        let mut recurrent = 0usize;
+        let mut on_lines = 0usize;
+        for offset in 1..n {
+            let mut run = 0usize;
+            for i in 0..(n - offset) {
+                if self.is_recurrent(i, i + offset) {
+                    recurrent += 1;
+                    run += 1;
+                } else {
+                    if run >= min_diagonal {
+                        on_lines += run;
+                    }
+                    run = 0;
+                }
+            }
+            if run >= min_diagonal {
+                on_lines += run;
+            }
+        }
+
+        let pairs = n * (n - 1) / 2;
+        self.recurrence_rate = recurrent as f32 / pairs as f32;
+        self.determinism = if recurrent > 0 {
+            on_lines as f32 / recurrent as f32
+        } else {
+            0.0
+        };
+    }
+
+    /// Write the buffered matrix into the plot texture (time runs left to right and bottom to top)
+    fn write_texture(&self, image: &mut Image) {
+        let window = self.params.window;
+        let on = self.params.recurrence_color.to_srgba().to_u8_array();
+        let off = self.params.background_color.to_srgba().to_u8_array();
+        let n = self.len();
+
+        let Some(data) = image.data.as_mut() else {
+            return;
+        };
+        for row in 0..window {
+            let j = window - 1 - row;
+            for i in 0..window {
+                let pixel = if i < n && j < n && self.is_recurrent(i, j) { on } else { off };
+                let offset = (row * window + i) * 4;
+                data[offset..offset + 4].copy_from_slice(&pixel);
+            }
+        }
+    }
+}
+
+/// System to refresh the recurrence textures and quantification numbers
+pub fn update_recurrence_plot(
+    mut query: Query<&mut RecurrencePlot>,
+    mut images: ResMut<Assets<Image>>,
+    mut q_text: Query<&mut Text2d>,
+) {
+    for mut plot in query.iter_mut() {
+        if plot.kept_since_update < plot.params.update_interval.max(1) {
+            continue;
+        }
+        plot.kept_since_update = 0;
+        plot.quantify();
+
+        if let Some(image) = images.get_mut(&plot.image) {
+            plot.write_texture(image);
+        }
+
+        if let Ok(mut text) = q_text.get_mut(plot.stats_text) {
+            text.0 = format!("RR = {:.3}   DET = {:.3}", plot.recurrence_rate, plot.determinism);
+        }
+    }
+}
+
+/// Spawn a recurrence plot entity (rendered as a sprite with its labels)
+pub fn spawn_recurrence_plot(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    params: RecurrenceParams,
+) -> Entity {
+    let window = params.window.max(2);
+    let params = RecurrenceParams { window, ..params };
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: window as u32,
+            height: window as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &params.background_color.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    // One texel per state pair, keep them crisp
+    image.sampler = ImageSampler::nearest();
+    let image_handle = images.add(image);
+
+    let pos = params.position;
+    let size = params.size;
+
+    commands.spawn((
+        Text2d::new(&params.label),
+        TextFont {
+            font_size: params.font_size,
+            ..default()
+        },
+        TextColor(params.text_color),
+        Transform::from_translation(Vec3::new(pos.x + 5.0, pos.y + 15.0, 0.2)),
+        Anchor::TOP_LEFT,
+    ));
+
+    let stats_text = commands.spawn((
+        Text2d::new(""),
+        TextFont {
+            font_size: params.font_size,
+            ..default()
+        },
+        TextColor(params.text_color),
+        Transform::from_translation(Vec3::new(pos.x, pos.y - size.y - 3.0, 0.2)),
+        Anchor::TOP_LEFT,
+    )).id();
+
+    commands.spawn((
+        Sprite {
+            image: image_handle.clone(),
+            custom_size: Some(size),
+            ..default()
+        },
+        Anchor::TOP_LEFT,
+        Transform::from_translation(Vec3::new(pos.x, pos.y, 0.0)),
+        RecurrencePlot {
+            states: vec![Vec::new(); window],
+            matrix: vec![false; window * window],
+            kept: 0,
+            pushed: 0,
+            kept_since_update: 0,
+            recurrence_rate: 0.0,
+            determinism: 0.0,
+            image: image_handle,
+            stats_text,
+            params,
+        },
+        Name::new("RecurrencePlot"),
+    )).id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plot(window: usize, threshold: f32, downsample: usize) -> RecurrencePlot {
+        RecurrencePlot {
+            params: RecurrenceParams { window, threshold, downsample, ..default() },
+            states: vec![Vec::new(); window],
+            matrix: vec![false; window * window],
+            kept: 0,
+            pushed: 0,
+            kept_since_update: 0,
+            recurrence_rate: 0.0,
+            determinism: 0.0,
+            image: Handle::default(),
+            stats_text: Entity::PLACEHOLDER,
+        }
+    }
+
+    /// Deterministic pseudo-random values in [0, 1)
+    fn samples(n: usize) -> Vec<f32> {
+        let mut state = 2463534242u32;
+        (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state >> 8) as f32 / (1 << 24) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn periodic_signal_is_deterministic() {
+        let (window, period) = (100, 20);
+        let mut plot = plot(window, 0.1, 1);
+        for k in 0..window {
+            let phase = 2.0 * std::f32::consts::PI * k as f32 / period as f32;
+            plot.push_state(&[phase.cos(), phase.sin()]);
+        }
+        plot.quantify();
+        // Only states a whole number of periods apart recur, along full diagonals
+        let recurrent: usize = (period..window).step_by(period).map(|offset| window - offset).sum();
+        let pairs = window * (window - 1) / 2;
+        assert!((plot.recurrence_rate - recurrent as f32 / pairs as f32).abs() < 1e-6, "RR = {}", plot.recurrence_rate);
+        assert!((plot.determinism - 1.0).abs() < 1e-6, "DET = {}", plot.determinism);
+    }
+
+    #[test]
+    fn isolated_recurrences_are_not_deterministic() {
+        let mut plot = plot(10, 0.5, 1);
+        for k in 0..9 {
+            plot.push_state(&[k as f32]);
+        }
+        plot.push_state(&[0.0]);
+        plot.quantify();
+        assert!(plot.is_recurrent(0, 9) && plot.is_recurrent(9, 0));
+        assert!((plot.recurrence_rate - 1.0 / 45.0).abs() < 1e-6, "RR = {}", plot.recurrence_rate);
+        assert_eq!(plot.determinism, 0.0);
+    }
+
+    #[test]
+    fn matrix_follows_the_ring_buffer_wraparound() {
+        let (window, threshold) = (8, 0.2);
+        let values = samples(23);
+        let mut plot = plot(window, threshold, 1);
+        for &value in &values {
+            plot.push_state(&[value]);
+        }
+        assert_eq!(plot.len(), window);
+
+        // Buffered states are the last `window` pushed, oldest first
+        let buffered = &values[values.len() - window..];
+        for i in 0..window {
+            for j in 0..window {
+                let expected = (buffered[i] - buffered[j]).abs() <= threshold;
+                assert_eq!(plot.is_recurrent(i, j), expected, "pair ({}, {})", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn downsample_keeps_one_state_in_n() {
+        let mut plot = plot(50, 0.1, 3);
+        assert!(plot.is_empty());
+        for k in 0..30 {
+            plot.push_state(&[k as f32]);
+        }
+        assert_eq!(plot.len(), 10);
+        // The first state pushed is kept, then every third
+        assert_eq!(plot.states[1], vec![3.0]);
+    }
+}
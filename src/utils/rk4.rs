@@ -1,59 +1,56 @@
 use crate::utils::ODEs::ODEFunc;
-
-pub struct RK4Prealloc {
-    pub y0: Vec<f32>,
-    pub k1: Vec<f32>,
-    pub k2: Vec<f32>,
-    pub k3: Vec<f32>,
-    pub k4: Vec<f32>,
-    pub out: Vec<f32>,
-
-    pub func: Box<dyn ODEFunc + Send + Sync>,
+use crate::utils::integrator::Integrator;
+
+/// Classic fourth order Runge-Kutta scheme with preallocated stages
+#[derive(Default)]
+pub struct RK4 {
+    k1: Vec<f32>,
+    k2: Vec<f32>,
+    k3: Vec<f32>,
+    k4: Vec<f32>,
+    tmp: Vec<f32>,
 }
 
-pub fn rk4(
-    t: f32,
-    dt: f32,
-    rk_params: &mut RK4Prealloc
-) {
-    let ode = &*rk_params.func;
-    let y = &rk_params.y0;
-    let k1 = &mut rk_params.k1;
-    let k2 = &mut rk_params.k2;
-    let k3 = &mut rk_params.k3;
-    let k4 = &mut rk_params.k4;
-    let out = &mut rk_params.out;
-
-
-
-
-    let n = y.len();
-    let half_dt = dt * 0.5;
-    let sixth = dt / 6.0;
-
-    // k1
-    ode.call(t, y, k1);
-
-    // k2 input: y + (dt/2)*k1
-    for i in 0..n {
-        out[i] = y[i] + half_dt * k1[i];
-    }
-    ode.call(t + half_dt, &out, k2);
-
-    // k3 input: y + (dt/2)*k2
-    for i in 0..n {
-        out[i] = y[i] + half_dt * k2[i];
+impl RK4 {
+    pub fn new(n: usize) -> Self {
+        Self {
+            k1: vec![0.0; n],
+            k2: vec![0.0; n],
+            k3: vec![0.0; n],
+            k4: vec![0.0; n],
+            tmp: vec![0.0; n],
+        }
     }
-    ode.call(t + half_dt, &out, k3);
-
-    // k4 input: y + dt*k3
-    for i in 0..n {
-        out[i] = y[i] + dt * k3[i];
-    }
-    ode.call(t + dt, &out, k4);
+}
 
-    // y_next = y + dt/6 * (k1 + 2*k2 + 2*k3 + k4)
-    for i in 0..n {
-        out[i] = y[i] + sixth * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+impl Integrator for RK4 {
+    fn step(&mut self, ode: &dyn ODEFunc, t: f32, dt: f32, y: &mut Vec<f32>) {
+        let n = y.len();
+        for buffer in [&mut self.k1, &mut self.k2, &mut self.k3, &mut self.k4, &mut self.tmp] {
+            buffer.resize(n, 0.0);
+        }
+        let half_dt = dt * 0.5;
+        let sixth = dt / 6.0;
+        // tmp = y + h * k
+        let offset = |tmp: &mut Vec<f32>, y: &[f32], h: f32, k: &[f32]| {
+            for ((tmp, y), k) in tmp.iter_mut().zip(y).zip(k) {
+                *tmp = y + h * k;
+            }
+        };
+
+        ode.call(t, y, &mut self.k1);
+
+        offset(&mut self.tmp, y, half_dt, &self.k1);
+        ode.call(t + half_dt, &self.tmp, &mut self.k2);
+
+        offset(&mut self.tmp, y, half_dt, &self.k2);
+        ode.call(t + half_dt, &self.tmp, &mut self.k3);
+
+        offset(&mut self.tmp, y, dt, &self.k3);
+        ode.call(t + dt, &self.tmp, &mut self.k4);
+
+        for (i, yi) in y.iter_mut().enumerate() {
+            *yi += sixth * (self.k1[i] + 2.0 * self.k2[i] + 2.0 * self.k3[i] + self.k4[i]);
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,145 @@
+// Ring buffer ribbon: each vertex carries its sample position, a side direction, the
+// simulated time it was sampled at and its arc length. Width, fade and color are computed
+// here from the age of the sample so that only the newest segment has to be uploaded every frame.
+//
+// RIBBON_3D is defined by `RibbonMaterial3d`: the side direction is then the tangent of the
+// ribbon and the vertices are pushed perpendicular to both the tangent and the view direction.
+
+#ifdef RIBBON_3D
+#import bevy_pbr::{
+    mesh_functions,
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+#else
+#import bevy_sprite::mesh2d_functions as mesh_functions
+#ifdef TONEMAP_IN_SHADER
+#import bevy_sprite::mesh2d_view_bindings::view
+#endif
+#endif
+
+#ifdef TONEMAP_IN_SHADER
+#import bevy_core_pipeline::tonemapping
+#endif
+
+const MAX_GRADIENT_STOPS: u32 = 16u;
+const CURVE_LUT_SIZE: u32 = 32u;
+
+struct RibbonSettings {
+    color: vec4<f32>,
+    gradient: array<vec4<f32>, MAX_GRADIENT_STOPS>,
+    gradient_len: u32,
+    gradient_axis: u32,
+    gradient_length: f32,
+    scalar_min: f32,
+    scalar_max: f32,
+    glow: f32,
+    head: f32,
+    head_arc_length: f32,
+    max_age: f32,
+    width: f32,
+    // Width (x), alpha (y) and color (z) curves from the tail to the head
+    curves: array<vec4<f32>, CURVE_LUT_SIZE>,
+    fade: u32,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> settings: RibbonSettings;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) side: vec4<f32>,
+    @location(2) birth: f32,
+    @location(3) arc_length: f32,
+    @location(4) scalar: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) progress: f32,
+    // Position along the colormap, 0 at the head
+    @location(1) gradient_t: f32,
+};
+
+// 0 at the tail, 1 at the head
+fn ribbon_progress(birth: f32) -> f32 {
+    let age = settings.head - birth;
+    return clamp(1.0 - age / settings.max_age, 0.0, 1.0);
+}
+
+// Linear interpolation in the curve lookup table
+fn lookup_curves(t: f32) -> vec4<f32> {
+    let x = clamp(t, 0.0, 1.0) * f32(CURVE_LUT_SIZE - 1u);
+    let i = min(u32(floor(x)), CURVE_LUT_SIZE - 2u);
+    return mix(settings.curves[i], settings.curves[i + 1u], x - f32(i));
+}
+
+// Curves at `progress`, zero past the tail so expired samples stay invisible
+fn sample_curves(progress: f32) -> vec4<f32> {
+    if progress <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    return lookup_curves(progress);
+}
+
+// Linear interpolation between evenly spaced stops
+fn sample_gradient(t: f32) -> vec4<f32> {
+    if settings.gradient_len <= 1u {
+        return settings.gradient[0];
+    }
+    let last = settings.gradient_len - 1u;
+    let x = clamp(t, 0.0, 1.0) * f32(last);
+    let i = min(u32(floor(x)), last - 1u);
+    return mix(settings.gradient[i], settings.gradient[i + 1u], x - f32(i));
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let progress = ribbon_progress(vertex.birth);
+    let half_width = 0.5 * settings.width * sample_curves(progress).x;
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+
+    var out: VertexOutput;
+#ifdef RIBBON_3D
+    let center = (world_from_local * vec4<f32>(vertex.position, 1.0)).xyz;
+    let tangent = (world_from_local * vec4<f32>(vertex.side.xyz, 0.0)).xyz;
+    let across = cross(tangent, view.world_position - center);
+    let across_length = length(across);
+    // Seen exactly along its tangent the ribbon has no width to show
+    var direction = vec3<f32>(0.0);
+    if across_length > 1.0e-6 {
+        direction = across / across_length;
+    }
+    out.clip_position = position_world_to_clip(center + direction * vertex.side.w * half_width);
+#else
+    let local = vertex.position + vertex.side.xyz * vertex.side.w * half_width;
+    let world_position = mesh_functions::mesh2d_position_local_to_world(world_from_local, vec4<f32>(local, 1.0));
+    out.clip_position = mesh_functions::mesh2d_position_world_to_clip(world_position);
+#endif
+    out.progress = progress;
+    if settings.gradient_axis == 2u {
+        let span = max(settings.scalar_max - settings.scalar_min, 1.0e-6);
+        out.gradient_t = lookup_curves((vertex.scalar - settings.scalar_min) / span).z;
+    } else {
+        var gradient_progress = progress;
+        if settings.gradient_axis == 1u {
+            gradient_progress = 1.0 - clamp((settings.head_arc_length - vertex.arc_length) / settings.gradient_length, 0.0, 1.0);
+        }
+        out.gradient_t = 1.0 - lookup_curves(gradient_progress).z;
+    }
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var alpha = 0.25;
+    if settings.fade != 0u {
+        alpha = sample_curves(in.progress).y / 4.0;
+    }
+    let tint = settings.color * sample_gradient(in.gradient_t);
+    var color = vec4<f32>(tint.rgb * settings.glow, tint.a * alpha);
+#ifdef TONEMAP_IN_SHADER
+    color = tonemapping::tone_mapping(color, view.color_grading);
+#endif
+    return color;
+}
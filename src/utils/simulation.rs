@@ -0,0 +1,131 @@
+use crate::utils::integrator::Integrator;
+use crate::utils::ODEs::ODEFunc;
+
+/// Plain-Rust simulation: an ODE, an integrator, the current time and state.
+/// Independent of Bevy, so it can run in tests and batch jobs; the apps wrap it in a resource.
+pub struct Simulation {
+    pub ode: Box<dyn ODEFunc + Send + Sync>,
+    pub integrator: Box<dyn Integrator + Send + Sync>,
+    /// Maximum integration step (in simulated time)
+    pub dt: f32,
+    /// Kept in f64 so long runs do not accumulate the rounding of `t += dt` in f32
+    t: f64,
+    state: Vec<f32>,
+}
+
+impl Simulation {
+    pub fn new(
+        ode: Box<dyn ODEFunc + Send + Sync>,
+        integrator: Box<dyn Integrator + Send + Sync>,
+        y0: Vec<f32>,
+        dt: f32,
+    ) -> Self {
+        assert!(dt.is_finite() && dt > 0.0, "Simulation step must be finite and positive, got {}", dt);
+        Self {
+            ode,
+            integrator,
+            dt,
+            t: 0.0,
+            state: y0,
+        }
+    }
+
+    /// Current simulated time
+    pub fn time(&self) -> f32 {
+        self.t as f32
+    }
+
+    /// Current state vector
+    pub fn state(&self) -> &[f32] {
+        &self.state
+    }
+
+    /// Reset the simulation to state `y` at time `t`
+    pub fn set_state(&mut self, t: f32, y: &[f32]) {
+        self.t = t as f64;
+        self.state.clear();
+        self.state.extend_from_slice(y);
+    }
+
+    /// Advance by a single step of `dt`
+    pub fn step(&mut self) {
+        self.step_by(self.dt as f64);
+    }
+
+    fn step_by(&mut self, dt: f64) {
+        self.integrator.step(&*self.ode, self.t as f32, dt as f32, &mut self.state);
+        self.t += dt;
+    }
+
+    /// Advance by `duration`, in steps of at most `dt` (the last one is shortened to land exactly)
+    pub fn advance(&mut self, duration: f32) {
+        let t_end = self.t + duration as f64;
+        self.integrate_to(t_end, |_, _| {});
+    }
+
+    /// Integrate until time `t_end`, calling `observer(t, state)` after every step
+    pub fn run_until<F: FnMut(f32, &[f32])>(&mut self, t_end: f32, observer: F) {
+        self.integrate_to(t_end as f64, observer);
+    }
+
+    fn integrate_to<F: FnMut(f32, &[f32])>(&mut self, t_end: f64, mut observer: F) {
+        let dt = self.dt as f64;
+        // Ignore leftovers much smaller than a step (floating point accumulation)
+        let tolerance = dt * 1e-3;
+        while t_end - self.t > tolerance {
+            self.step_by(dt.min(t_end - self.t));
+            observer(self.t as f32, &self.state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rk4::RK4;
+
+    /// Unit harmonic oscillator x'' = -x, state [x, v]
+    struct Oscillator;
+
+    impl ODEFunc for Oscillator {
+        fn call(&self, _t: f32, y: &Vec<f32>, out: &mut Vec<f32>) {
+            out[0] = y[1];
+            out[1] = -y[0];
+        }
+    }
+
+    #[test]
+    fn rk4_follows_harmonic_oscillator() {
+        let mut sim = Simulation::new(Box::new(Oscillator), Box::new(RK4::new(2)), vec![1.0, 0.0], 0.01);
+        let mut steps = 0;
+        sim.run_until(10.0, |t, y| {
+            steps += 1;
+            assert!((y[0] - t.cos()).abs() < 1e-4, "x({}) = {}, expected {}", t, y[0], t.cos());
+            assert!((y[1] + t.sin()).abs() < 1e-4, "v({}) = {}, expected {}", t, y[1], -t.sin());
+        });
+        assert_eq!(steps, 1000);
+        assert_eq!(sim.time(), 10.0);
+    }
+
+    #[test]
+    fn long_runs_land_on_the_end_time() {
+        let mut sim = Simulation::new(Box::new(Oscillator), Box::new(RK4::new(2)), vec![1.0, 0.0], 1e-3);
+        let mut steps = 0;
+        let mut last_dt = 0.0;
+        let mut last_t = 0.0;
+        sim.run_until(100.0, |t, _| {
+            steps += 1;
+            last_dt = t - last_t;
+            last_t = t;
+        });
+        assert_eq!(steps, 100_000);
+        assert!((last_dt - 1e-3).abs() < 1e-5, "last step {}", last_dt);
+        assert_eq!(sim.time(), 100.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_non_finite_step() {
+        Simulation::new(Box::new(Oscillator), Box::new(RK4::new(2)), vec![1.0, 0.0], f32::NAN);
+    }
+}
@@ -0,0 +1,465 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_vector_shapes::prelude::*;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+use crate::utils::graph::{
+    draw_single_graph, mesh_marks, AxisScale, GraphLabel, GraphMeshes, GraphParams, GraphWidget, GridlineConfig, LabelQuery,
+};
+
+/// Window applied to the rolling buffer before the FFT to limit spectral leakage
+#[derive(Debug, Clone, Copy)]
+pub enum WindowFunction {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl WindowFunction {
+    /// Weight of sample `i` in a window of length `n`
+    pub fn weight(&self, i: usize, n: usize) -> f32 {
+        if n < 2 {
+            return 1.0;
+        }
+        let x = 2.0 * PI * i as f32 / (n - 1) as f32;
+        match self {
+            WindowFunction::Rectangular => 1.0,
+            WindowFunction::Hann => 0.5 - 0.5 * x.cos(),
+            WindowFunction::Hamming => 0.54 - 0.46 * x.cos(),
+            WindowFunction::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub fn norm_sqr(&self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+/// In-place iterative radix-2 FFT, `data.len()` must be a power of two
+pub fn fft(data: &mut [Complex]) {
+    let n = data.len();
+    assert!(n.is_power_of_two(), "FFT length must be a power of two");
+    if n < 2 {
+        return;
+    }
+
+    // Bit-reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            data.swap(i, j);
+        }
+    }
+
+    // Butterflies
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        let (w_im, w_re) = angle.sin_cos();
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+            for k in 0..len / 2 {
+                let a = data[start + k];
+                let b = data[start + k + len / 2];
+                let t = Complex {
+                    re: b.re * cur_re - b.im * cur_im,
+                    im: b.re * cur_im + b.im * cur_re,
+                };
+                data[start + k] = Complex { re: a.re + t.re, im: a.im + t.im };
+                data[start + k + len / 2] = Complex { re: a.re - t.re, im: a.im - t.im };
+
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Rolling buffer of uniformly sampled scalars and its windowed power spectrum
+pub struct SpectrumAnalyzer {
+    pub window: WindowFunction,
+    /// Sampling rate of the pushed values (in Hz of simulated time)
+    pub sample_rate: f32,
+    samples: VecDeque<f32>,
+    size: usize,
+    scratch: Vec<Complex>,
+}
+
+impl SpectrumAnalyzer {
+    /// `size` is rounded up to the next power of two
+    pub fn new(size: usize, sample_rate: f32, window: WindowFunction) -> Self {
+        let size = size.max(2).next_power_of_two();
+        Self {
+            window,
+            sample_rate,
+            samples: VecDeque::with_capacity(size),
+            size,
+            scratch: vec![Complex::default(); size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.samples.len() == self.size
+    }
+
+    pub fn push(&mut self, value: f32) {
+        self.samples.push_back(value);
+        if self.samples.len() > self.size {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// One-sided power spectrum as (frequency, power) pairs, from DC to Nyquist.
+    /// The buffer mean is removed first so the DC bin does not swamp the plot.
+    /// Missing samples (buffer not yet full) are zero-padded.
+    pub fn power_spectrum(&mut self, out: &mut Vec<(f32, f32)>) {
+        out.clear();
+        let n = self.size;
+        let count = self.samples.len();
+        if count == 0 {
+            return;
+        }
+
+        let mean = self.samples.iter().sum::<f32>() / count as f32;
+        let mut window_sum = 0.0;
+        for i in 0..n {
+            self.scratch[i] = if i < count {
+                let w = self.window.weight(i, count);
+                window_sum += w;
+                Complex { re: (self.samples[i] - mean) * w, im: 0.0 }
+            } else {
+                Complex::default()
+            };
+        }
+
+        fft(&mut self.scratch);
+
+        let norm = 1.0 / (window_sum * window_sum).max(f32::EPSILON);
+        let bin_width = self.sample_rate / n as f32;
+        for k in 0..=n / 2 {
+            // Fold negative frequencies into the positive half (except DC and Nyquist)
+            let scale = if k == 0 || k == n / 2 { 1.0 } else { 2.0 };
+            out.push((k as f32 * bin_width, scale * self.scratch[k].norm_sqr() * norm));
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SpectrumParams {
+    /// Layout and styling of the underlying plot
+    pub graph: GraphParams,
+    /// Number of samples in the rolling buffer (rounded up to a power of two)
+    pub buffer_size: usize,
+    /// Rate at which values are pushed into the widget (in Hz)
+    pub sample_rate: f32,
+    /// Keep one value out of every `downsample` pushed (1 keeps all of them)
+    pub downsample: usize,
+    pub window: WindowFunction,
+    /// Recompute the spectrum every `update_interval` kept samples
+    pub update_interval: usize,
+    /// Span of the log-magnitude axis below the strongest bin (in dB)
+    pub dynamic_range_db: f32,
+    /// Maximum number of peaks to label
+    pub num_peaks: usize,
+    /// Peaks weaker than this (in dB below the strongest bin) are not labeled
+    pub peak_threshold_db: f32,
+    /// Color of the peak markers and labels
+    pub peak_color: Color,
+}
+
+impl Default for SpectrumParams {
+    fn default() -> Self {
+        Self {
+            graph: GraphParams {
+                label: "Power spectrum (dB)".to_string(),
+                x_title: "f (Hz)".to_string(),
+                x_gridlines: GridlineConfig::Dynamic {
+                    min_spacing: 0.5,
+                    num_lines: 4,
+                },
+                y_gridlines: GridlineConfig::Dynamic {
+                    min_spacing: 10.0,
+                    num_lines: 4,
+                },
+                show_current_y: false,
+                ..Default::default()
+            },
+            buffer_size: 1024,
+            sample_rate: 60.0,
+            downsample: 1,
+            window: WindowFunction::Hann,
+            update_interval: 16,
+            dynamic_range_db: 80.0,
+            num_peaks: 3,
+            peak_threshold_db: 30.0,
+            peak_color: Color::srgba(0.9, 0.9, 0.9, 1.0),
+        }
+    }
+}
+
+/// Spectrum variant of `GraphWidget`: plots the power spectrum (in dB) of a scalar
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub struct SpectrumWidget {
+    pub params: SpectrumParams,
+    pub analyzer: SpectrumAnalyzer,
+    /// Plot holding the current spectrum as (frequency, dB) points
+    pub graph: GraphWidget,
+    /// Labeled peaks as (frequency, dB), strongest first
+    pub peaks: Vec<(f32, f32)>,
+    pushed: usize,
+    kept_since_update: usize,
+    spectrum: Vec<(f32, f32)>,
+}
+
+impl SpectrumWidget {
+    pub fn new(params: SpectrumParams) -> Self {
+        let downsample = params.downsample.max(1);
+        let analyzer = SpectrumAnalyzer::new(
+            params.buffer_size,
+            params.sample_rate / downsample as f32,
+            params.window,
+        );
+        let mut graph_params = params.graph.clone();
+        graph_params.max_points = analyzer.size() / 2 + 1;
+
+        Self {
+            graph: GraphWidget::new(graph_params),
+            analyzer,
+            params,
+            peaks: Vec::new(),
+            pushed: 0,
+            kept_since_update: 0,
+            spectrum: Vec::new(),
+        }
+    }
+
+    /// Push a new sample of the observed scalar
+    pub fn add_sample(&mut self, value: f32) {
+        let downsample = self.params.downsample.max(1);
+        self.pushed += 1;
+        if !(self.pushed - 1).is_multiple_of(downsample) {
+            return;
+        }
+
+        self.analyzer.push(value);
+        self.kept_since_update += 1;
+        // A frozen plot keeps its spectrum, the analyzer still follows the signal
+        if self.kept_since_update >= self.params.update_interval.max(1) && !self.graph.frozen {
+            self.kept_since_update = 0;
+            self.recompute();
+        }
+    }
+
+    /// Recompute the spectrum, the plot ranges and the peak list
+    pub fn recompute(&mut self) {
+        self.analyzer.power_spectrum(&mut self.spectrum);
+        if self.spectrum.len() < 3 {
+            return;
+        }
+
+        // Skip the DC bin, it only holds what is left of the mean
+        let floor = f32::MIN_POSITIVE;
+        let db: Vec<(f32, f32)> = self.spectrum[1..]
+            .iter()
+            .map(|&(f, p)| (f, 10.0 * p.max(floor).log10()))
+            .collect();
+
+        let max_db = db.iter().map(|&(_, d)| d).fold(f32::MIN, f32::max);
+        let min_db = max_db - self.params.dynamic_range_db;
+
+        let series = &mut self.graph.series[0];
+        series.data.clear();
+        series.data.extend(db.iter().map(|&(f, d)| (f, d.max(min_db))));
+        // A log frequency axis starts at the first bin above DC
+        self.graph.x_min = match self.graph.params.x_scale {
+            AxisScale::Linear => 0.0,
+            AxisScale::Log => db[0].0,
+        };
+        self.graph.x_max = self.analyzer.sample_rate / 2.0;
+        self.graph.y_min = min_db;
+        self.graph.y_max = max_db + 0.05 * self.params.dynamic_range_db;
+
+        // Local maxima above the threshold, strongest first
+        let threshold = max_db - self.params.peak_threshold_db;
+        let mut candidates: Vec<(f32, f32)> = db
+            .windows(3)
+            .filter(|w| w[1].1 > w[0].1 && w[1].1 >= w[2].1 && w[1].1 >= threshold)
+            .map(|w| w[1])
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // Drop peaks that sit in the skirt of a stronger one
+        let min_separation = 3.0 * self.analyzer.sample_rate / self.analyzer.size() as f32;
+        self.peaks.clear();
+        for candidate in candidates {
+            if self.peaks.len() >= self.params.num_peaks {
+                break;
+            }
+            if self.peaks.iter().all(|p| (p.0 - candidate.0).abs() > min_separation) {
+                self.peaks.push(candidate);
+            }
+        }
+    }
+}
+
+/// System to draw the spectrum widgets and their peak labels
+pub fn draw_spectrum_widget(
+    mut commands: Commands,
+    mut painter: ShapePainter,
+    mut meshes: GraphMeshes,
+    mut query: Query<(Entity, &mut SpectrumWidget)>,
+    mut q_label: LabelQuery,
+) {
+    for (entity, mut spectrum) in query.iter_mut() {
+        let spectrum = &mut *spectrum;
+        let mut layout = draw_single_graph(&mut painter, &spectrum.graph);
+
+        let font_size = spectrum.graph.params.font_size * 0.8;
+        let peak_color = spectrum.params.peak_color;
+        for &(frequency, db) in &spectrum.peaks {
+            let screen_pos = spectrum.graph.to_screen(frequency, db);
+            if !spectrum.graph.contains(screen_pos) {
+                continue;
+            }
+
+            let base = painter.transform;
+            painter.set_color(peak_color);
+            painter.hollow = false;
+            painter.translate(Vec3::new(screen_pos.x, screen_pos.y, 0.15));
+            painter.circle(2.5);
+            painter.transform = base;
+
+            layout.labels.push(GraphLabel {
+                text: format!("{:.2} Hz", frequency),
+                position: Vec2::new(screen_pos.x, screen_pos.y + 4.0),
+                font_size,
+                color: peak_color,
+                anchor: Anchor::BOTTOM_CENTER,
+                rotation: 0.0,
+            });
+        }
+
+        let layers = spectrum.graph.render_layers();
+        let mesh_marks = mesh_marks(&spectrum.graph, &layout);
+        spectrum.graph.series_mesh.sync(&mut commands, &mut meshes, entity, mesh_marks, &layers);
+        spectrum.graph.labels.sync(&mut commands, entity, &layout.labels, &layers, &mut q_label);
+    }
+}
+
+/// Spawn a spectrum widget entity
+pub fn spawn_spectrum_widget(
+    commands: &mut Commands,
+    params: SpectrumParams,
+) -> Entity {
+    commands.spawn((
+        SpectrumWidget::new(params),
+        Name::new("SpectrumWidget"),
+    )).id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sine of `amplitude` completing `cycles` periods over `n` samples
+    fn sine(n: usize, cycles: f32, amplitude: f32) -> impl Iterator<Item = f32> {
+        (0..n).map(move |i| amplitude * (2.0 * PI * cycles * i as f32 / n as f32).sin())
+    }
+
+    #[test]
+    fn fft_peaks_at_the_sinusoid_bin() {
+        let n = 256;
+        let mut data: Vec<Complex> = sine(n, 10.0, 1.0).map(|re| Complex { re, im: 0.0 }).collect();
+        fft(&mut data);
+        // A real sine splits between bin k and its mirror n - k, each of magnitude n / 2
+        for (k, bin) in data.iter().enumerate() {
+            let expected = if k == 10 || k == n - 10 { (n / 2) as f32 } else { 0.0 };
+            assert!((bin.norm_sqr().sqrt() - expected).abs() < 1e-3, "bin {} is {:?}", k, bin);
+        }
+    }
+
+    #[test]
+    fn fft_preserves_energy() {
+        let n = 512;
+        // Deterministic pseudo-random complex input
+        let mut state = 12345u32;
+        let mut next = || {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            (state >> 8) as f32 / (1 << 24) as f32 - 0.5
+        };
+        let mut data: Vec<Complex> = (0..n).map(|_| Complex { re: next(), im: next() }).collect();
+        let time_energy: f32 = data.iter().map(Complex::norm_sqr).sum();
+        fft(&mut data);
+        let frequency_energy: f32 = data.iter().map(Complex::norm_sqr).sum::<f32>() / n as f32;
+        assert!(
+            (time_energy - frequency_energy).abs() < 1e-4 * time_energy,
+            "Parseval: {} vs {}",
+            time_energy,
+            frequency_energy
+        );
+    }
+
+    #[test]
+    fn power_spectrum_finds_the_sinusoid_frequency() {
+        let (n, sample_rate) = (256, 64.0);
+        let mut analyzer = SpectrumAnalyzer::new(n, sample_rate, WindowFunction::Rectangular);
+        // Offset to check the mean is removed, 12 cycles over the buffer is 3 Hz
+        for value in sine(n, 12.0, 2.0) {
+            analyzer.push(value + 5.0);
+        }
+        assert!(analyzer.is_full());
+
+        let mut spectrum = Vec::new();
+        analyzer.power_spectrum(&mut spectrum);
+        assert_eq!(spectrum.len(), n / 2 + 1);
+        assert_eq!(spectrum[n / 2].0, sample_rate / 2.0);
+        let (peak, &(frequency, power)) = spectrum
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.1.total_cmp(&b.1.1))
+            .unwrap();
+        assert_eq!(peak, 12);
+        assert_eq!(frequency, 3.0);
+        // Mean square of the sine, A² / 2
+        assert!((power - 2.0).abs() < 1e-3, "peak power {}", power);
+        assert!(spectrum[0].1 < 1e-6, "DC power {}", spectrum[0].1);
+    }
+
+    #[test]
+    fn window_weights() {
+        let n = 65;
+        for window in [WindowFunction::Hann, WindowFunction::Hamming, WindowFunction::Blackman] {
+            let middle = window.weight(n / 2, n);
+            assert!((middle - 1.0).abs() < 1e-5, "{:?} peaks at {}", window, middle);
+            for i in 0..n {
+                assert!((window.weight(i, n) - window.weight(n - 1 - i, n)).abs() < 1e-5, "{:?} is not symmetric", window);
+            }
+        }
+        assert!(WindowFunction::Hann.weight(0, n).abs() < 1e-6);
+        assert!((WindowFunction::Hamming.weight(0, n) - 0.08).abs() < 1e-6);
+        assert!(WindowFunction::Blackman.weight(0, n).abs() < 1e-6);
+        assert_eq!(WindowFunction::Rectangular.weight(7, n), 1.0);
+    }
+}